@@ -0,0 +1,395 @@
+use time::{Date, Duration, Month};
+
+use crate::util;
+
+/// A calendar that dates are formatted and parsed in, for `%Y`, `%m`, `%d`, `%j`, `%B` and the
+/// others of the year, the month and the day.
+///
+/// Weekdays, the week numbers and the ISO 8601 week-based year stay the same in every calendar.
+/// Months are numbered from `Month::January`, the first month of the year in the calendar.
+pub trait Calendar {
+    /// The year, the month and the day of `date` in this calendar.
+    fn calendar_date(&self, date: Date) -> (i32, Month, u8);
+
+    /// The date of the given day in this calendar, or `None` if there is no such day.
+    fn to_date(&self, year: i32, month: Month, day: u8) -> Option<Date>;
+
+    /// The day of the year `date` is in this calendar, starting from 1.
+    #[inline]
+    fn ordinal(&self, date: Date) -> u16 {
+        let (year, _, _) = self.calendar_date(date);
+        match self.to_date(year, Month::January, 1) {
+            Some(first) => ((date - first).whole_days() + 1) as u16,
+            None => date.ordinal(),
+        }
+    }
+
+    /// The date of the `ordinal`-th day of `year` in this calendar, or `None` if there is no such
+    /// day.
+    #[inline]
+    fn ordinal_to_date(&self, year: i32, ordinal: u16) -> Option<Date> {
+        let first = self.to_date(year, Month::January, 1)?;
+        let date = first.checked_add(Duration::days(i64::from(ordinal) - 1))?;
+        (ordinal >= 1 && self.calendar_date(date).0 == year).then_some(date)
+    }
+
+    /// The abbreviated name of `month` for `%b`, or `None` to use the name the locale has.
+    #[inline]
+    fn month_short(&self, _month: Month) -> Option<&str> {
+        None
+    }
+
+    /// The name of `month` for `%B`, or `None` to use the name the locale has.
+    #[inline]
+    fn month_long(&self, _month: Month) -> Option<&str> {
+        None
+    }
+}
+
+/// The proleptic Gregorian calendar `time::Date` is in, which is what locales use by default.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Gregorian;
+
+impl Calendar for Gregorian {
+    #[inline]
+    fn calendar_date(&self, date: Date) -> (i32, Month, u8) {
+        date.to_calendar_date()
+    }
+
+    #[inline]
+    fn to_date(&self, year: i32, month: Month, day: u8) -> Option<Date> {
+        Date::from_calendar_date(year, month, day).ok()
+    }
+
+    #[inline]
+    fn ordinal(&self, date: Date) -> u16 {
+        date.ordinal()
+    }
+
+    #[inline]
+    fn ordinal_to_date(&self, year: i32, ordinal: u16) -> Option<Date> {
+        Date::from_ordinal_date(year, ordinal).ok()
+    }
+}
+
+/// The Julian calendar, optionally switching over to the Gregorian one, for historical dates.
+///
+/// ```
+/// use time::macros::datetime;
+/// use time_fmt::{
+///     calendar::Julian,
+///     format::{format_date_time_with_locale, FormatOptions},
+///     locale::{PosixLocale, WithCalendar},
+/// };
+///
+/// let locale = WithCalendar::new(&PosixLocale, &Julian::GREGORIAN_REFORM);
+/// let fmt = "%a %Y-%m-%d";
+/// assert_eq!(
+///     format_date_time_with_locale(fmt, datetime!(1582-10-14 00:00), &locale, &FormatOptions::default()).unwrap(),
+///     "Thu 1582-10-04"
+/// );
+/// assert_eq!(
+///     format_date_time_with_locale(fmt, datetime!(1582-10-15 00:00), &locale, &FormatOptions::default()).unwrap(),
+///     "Fri 1582-10-15"
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Julian {
+    switchover: Option<Date>,
+}
+
+impl Julian {
+    /// The proleptic Julian calendar, never switching over to the Gregorian one.
+    pub const PROLEPTIC: Self = Self { switchover: None };
+
+    /// Switching over to the Gregorian calendar on 15 October 1582, when it was first adopted.
+    pub const GREGORIAN_REFORM: Self = Self::with_switchover(util::date(1582, Month::October, 15));
+
+    /// The Julian calendar until the day before `switchover`, and the Gregorian one from it, like
+    /// 14 September 1752 in Great Britain.
+    #[inline]
+    pub const fn with_switchover(switchover: Date) -> Self {
+        Self {
+            switchover: Some(switchover),
+        }
+    }
+
+    /// The first day in the Gregorian calendar, if any.
+    #[inline]
+    pub const fn switchover(&self) -> Option<Date> {
+        self.switchover
+    }
+
+    /// Whether `year` has 29 February in the Julian calendar.
+    #[inline]
+    pub const fn is_leap_year(year: i32) -> bool {
+        year.rem_euclid(4) == 0
+    }
+
+    #[inline]
+    fn is_gregorian(&self, date: Date) -> bool {
+        self.switchover.is_some_and(|switchover| switchover <= date)
+    }
+
+    /// The year, the month and the day of `date` in the proleptic Julian calendar.
+    fn julian_date(date: Date) -> (i32, Month, u8) {
+        let c = i64::from(date.to_julian_day()) + 32_082;
+        let d = (4 * c + 3).div_euclid(1461);
+        let e = c - (1461 * d).div_euclid(4);
+        let m = (5 * e + 2) / 153;
+        let day = e - (153 * m + 2) / 5 + 1;
+        let month = m + 3 - 12 * (m / 10);
+        let year = d - 4800 + m / 10;
+        let month = util::get_month(month as u8).expect("a year has 12 months");
+        (year as i32, month, day as u8)
+    }
+
+    /// The date of the given day in the proleptic Julian calendar.
+    fn from_julian_date(year: i32, month: Month, day: u8) -> Option<Date> {
+        let days_in_month = match month {
+            Month::February if Self::is_leap_year(year) => 29,
+            Month::February => 28,
+            Month::April | Month::June | Month::September | Month::November => 30,
+            _ => 31,
+        };
+        if day < 1 || days_in_month < day {
+            return None;
+        }
+        let a = (14 - i64::from(month as u8)) / 12;
+        let y = i64::from(year) + 4800 - a;
+        let m = i64::from(month as u8) + 12 * a - 3;
+        let julian_day = i64::from(day) + (153 * m + 2) / 5 + 365 * y + y.div_euclid(4) - 32_083;
+        Date::from_julian_day(julian_day.try_into().ok()?).ok()
+    }
+}
+
+impl Calendar for Julian {
+    fn calendar_date(&self, date: Date) -> (i32, Month, u8) {
+        if self.is_gregorian(date) {
+            date.to_calendar_date()
+        } else {
+            Self::julian_date(date)
+        }
+    }
+
+    fn to_date(&self, year: i32, month: Month, day: u8) -> Option<Date> {
+        // Days skipped by the switchover are in neither.
+        if let Ok(date) = Date::from_calendar_date(year, month, day) {
+            if self.is_gregorian(date) {
+                return Some(date);
+            }
+        }
+        Self::from_julian_date(year, month, day).filter(|&date| !self.is_gregorian(date))
+    }
+}
+
+/// The Solar Hijri calendar of Iran, with the Persian month names.
+///
+/// Years are leap years by the arithmetic 33-year cycle, which agrees with the official
+/// astronomical one from 1178 to 1634 (1799 to 2256 in the Gregorian calendar).
+///
+/// ```
+/// use time::macros::datetime;
+/// use time_fmt::{
+///     calendar::SolarHijri,
+///     format::{format_date_time_with_locale, FormatOptions},
+///     locale::{PosixLocale, WithCalendar},
+/// };
+///
+/// let locale = WithCalendar::new(&PosixLocale, &SolarHijri);
+/// assert_eq!(
+///     format_date_time_with_locale("%Y/%m/%d %B", datetime!(2022-03-06 00:00), &locale, &FormatOptions::default()).unwrap(),
+///     "1400/12/15 اسفند"
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SolarHijri;
+
+impl SolarHijri {
+    /// The Julian day of 1 Farvardin of the year 1 in the arithmetic calendar.
+    const EPOCH: i64 = 1_948_320;
+    /// Days in 33 years, of which 8 are leap years.
+    const CYCLE_DAYS: i64 = 33 * 365 + 8;
+    const MONTH_NAMES: [&'static str; 12] = [
+        "فروردین",
+        "اردیبهشت",
+        "خرداد",
+        "تیر",
+        "مرداد",
+        "شهریور",
+        "مهر",
+        "آبان",
+        "آذر",
+        "دی",
+        "بهمن",
+        "اسفند",
+    ];
+
+    /// Whether `year` has 366 days, with the 30th of Esfand.
+    #[inline]
+    pub const fn is_leap_year(year: i32) -> bool {
+        (25 * year as i64 + 11).rem_euclid(33) < 8
+    }
+
+    /// The Julian day of 1 Farvardin of `year`.
+    #[inline]
+    fn new_year(year: i32) -> i64 {
+        let years = i64::from(year) - 1;
+        Self::EPOCH + 365 * years + (8 * years + 29).div_euclid(33)
+    }
+
+    #[inline]
+    fn from_julian_day(day: i64) -> Option<Date> {
+        Date::from_julian_day(day.try_into().ok()?).ok()
+    }
+
+    /// The year and the day of the year of `date`.
+    fn year_ordinal(date: Date) -> (i32, u16) {
+        let day = i64::from(date.to_julian_day());
+        let mut year = ((day - Self::EPOCH) * 33).div_euclid(Self::CYCLE_DAYS) as i32 + 1;
+        while Self::new_year(year + 1) <= day {
+            year += 1;
+        }
+        while Self::new_year(year) > day {
+            year -= 1;
+        }
+        (year, (day - Self::new_year(year) + 1) as u16)
+    }
+
+    #[inline]
+    const fn days_in_month(year: i32, month: Month) -> u8 {
+        match month as u8 {
+            1..=6 => 31,
+            7..=11 => 30,
+            _ if Self::is_leap_year(year) => 30,
+            _ => 29,
+        }
+    }
+}
+
+impl Calendar for SolarHijri {
+    fn calendar_date(&self, date: Date) -> (i32, Month, u8) {
+        let (year, ordinal) = Self::year_ordinal(date);
+        let (month, day) = if ordinal <= 186 {
+            ((ordinal - 1) / 31 + 1, (ordinal - 1) % 31 + 1)
+        } else {
+            ((ordinal - 187) / 30 + 7, (ordinal - 187) % 30 + 1)
+        };
+        let month = util::get_month(month as u8).expect("a year has 12 months");
+        (year, month, day as u8)
+    }
+
+    fn to_date(&self, year: i32, month: Month, day: u8) -> Option<Date> {
+        if day < 1 || Self::days_in_month(year, month) < day {
+            return None;
+        }
+        let month = u16::from(month as u8);
+        let ordinal = if month <= 6 {
+            (month - 1) * 31
+        } else {
+            186 + (month - 7) * 30
+        } + u16::from(day);
+        self.ordinal_to_date(year, ordinal)
+    }
+
+    #[inline]
+    fn ordinal(&self, date: Date) -> u16 {
+        Self::year_ordinal(date).1
+    }
+
+    #[inline]
+    fn ordinal_to_date(&self, year: i32, ordinal: u16) -> Option<Date> {
+        let days = if Self::is_leap_year(year) { 366 } else { 365 };
+        if ordinal < 1 || days < ordinal {
+            return None;
+        }
+        Self::from_julian_day(Self::new_year(year) + i64::from(ordinal) - 1)
+    }
+
+    #[inline]
+    fn month_short(&self, month: Month) -> Option<&str> {
+        self.month_long(month)
+    }
+
+    #[inline]
+    fn month_long(&self, month: Month) -> Option<&str> {
+        Some(Self::MONTH_NAMES[month as usize - 1])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Calendar, Julian, SolarHijri};
+    use crate::util::{date, get_month};
+
+    #[test]
+    fn test_solar_hijri() {
+        for ((y, m, d), (year, month, day), ordinal) in [
+            ((2022, 3, 6), (1400, 12, 15), 351),
+            ((2021, 3, 20), (1399, 12, 30), 366),
+            ((2021, 3, 21), (1400, 1, 1), 1),
+            ((2021, 9, 22), (1400, 6, 31), 186),
+            ((2021, 9, 23), (1400, 7, 1), 187),
+            ((2025, 3, 20), (1403, 12, 30), 366),
+            ((1979, 2, 11), (1357, 11, 22), 328),
+        ] {
+            let date = date(y, get_month(m).unwrap(), d);
+            let month = get_month(month).unwrap();
+            assert_eq!(
+                SolarHijri.calendar_date(date),
+                (year, month, day),
+                "{}",
+                date
+            );
+            assert_eq!(SolarHijri.ordinal(date), ordinal, "{}", date);
+            assert_eq!(SolarHijri.to_date(year, month, day), Some(date));
+            assert_eq!(SolarHijri.ordinal_to_date(year, ordinal), Some(date));
+        }
+        let esfand = get_month(12).unwrap();
+        assert_eq!(SolarHijri.to_date(1400, esfand, 30), None);
+        assert_eq!(SolarHijri.to_date(1400, get_month(7).unwrap(), 31), None);
+        assert_eq!(SolarHijri.ordinal_to_date(1400, 366), None);
+        let leap_years: Vec<_> = (1390..=1410)
+            .filter(|&year| SolarHijri::is_leap_year(year))
+            .collect();
+        assert_eq!(leap_years, [1391, 1395, 1399, 1403, 1408]);
+    }
+
+    #[test]
+    fn test_julian() {
+        let october = get_month(10).unwrap();
+        for (calendar, (y, m, d), (year, month, day), ordinal) in [
+            (Julian::PROLEPTIC, (2022, 3, 6), (2022, 2, 21), 52),
+            (Julian::PROLEPTIC, (1900, 3, 13), (1900, 2, 29), 60),
+            (Julian::PROLEPTIC, (1, 1, 1), (1, 1, 3), 3),
+            (Julian::PROLEPTIC, (-100, 3, 1), (-100, 3, 3), 63),
+            (Julian::GREGORIAN_REFORM, (1582, 10, 14), (1582, 10, 4), 277),
+            (
+                Julian::GREGORIAN_REFORM,
+                (1582, 10, 15),
+                (1582, 10, 15),
+                278,
+            ),
+            (Julian::GREGORIAN_REFORM, (2022, 3, 6), (2022, 3, 6), 65),
+            (Julian::GREGORIAN_REFORM, (1500, 3, 10), (1500, 2, 29), 60),
+        ] {
+            let date = date(y, get_month(m).unwrap(), d);
+            let month = get_month(month).unwrap();
+            assert_eq!(calendar.calendar_date(date), (year, month, day), "{}", date);
+            assert_eq!(calendar.ordinal(date), ordinal, "{}", date);
+            assert_eq!(calendar.to_date(year, month, day), Some(date));
+            assert_eq!(calendar.ordinal_to_date(year, ordinal), Some(date));
+        }
+        // Skipped by the switchover.
+        assert_eq!(Julian::GREGORIAN_REFORM.to_date(1582, october, 10), None);
+        assert_eq!(
+            Julian::PROLEPTIC.to_date(1582, october, 10),
+            Some(date(1582, october, 20))
+        );
+        assert_eq!(Julian::GREGORIAN_REFORM.ordinal_to_date(1582, 356), None);
+        assert_eq!(
+            Julian::GREGORIAN_REFORM.ordinal_to_date(1583, 1),
+            Some(date(1583, get_month(1).unwrap(), 1))
+        );
+    }
+}
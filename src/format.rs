@@ -1,27 +1,83 @@
-use std::fmt::Write;
+use std::{fmt::Write, io};
 
 use thiserror::Error;
-use time::{Date, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset};
+use time::{Date, Duration, Month, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset};
 
-use crate::{format::spec_parser::Collector, util};
+use crate::{
+    format::spec_parser::{Collector, Padding, Spec},
+    locale::{AltDigits, Era, Expanding, Locale, LocaleFormat, PosixLocale},
+    util::Modifier,
+};
 
+mod compiled;
+mod display;
 mod spec_parser;
+// Components of `FormatItem` we rely on are deprecated in newer versions of the time crate, but we
+// keep using them to support the older ones.
+#[allow(deprecated)]
 pub mod time_format_item;
 
+pub use compiled::StrftimeFormat;
+pub use display::Strftime;
+
 #[derive(Error, Debug, PartialEq, Eq)]
 #[non_exhaustive]
 pub enum FormatError {
     #[error("Unknown specifier `%{0}`")]
     UnknownSpecifier(char),
+    #[error("Field width {1} is not supported by `%{0}`")]
+    UnsupportedWidth(char, usize),
+    #[error("Precision {1} is not supported by `%{0}`")]
+    UnsupportedPrecision(char, usize),
+    #[error("`%s` requires an offset but the date time doesn't have one")]
+    MissingOffset,
+    #[error("Locale-provided format expands into itself")]
+    RecursiveLocaleFormat,
     #[error(transparent)]
     Format(#[from] std::fmt::Error),
 }
 
+/// Options that change how some conversions behave, taken by the `format_*_with_locale` functions
+/// and [`StrftimeFormat::with_options`]. The default is what the other `format_*` functions of
+/// this module do.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FormatOptions {
+    require_offset_for_timestamp: bool,
+    round_subseconds: bool,
+}
+
+impl FormatOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes `%s` fail with [`FormatError::MissingOffset`] for a date time without an offset,
+    /// instead of treating it as UTC.
+    pub fn require_offset_for_timestamp(mut self, require: bool) -> Self {
+        self.require_offset_for_timestamp = require;
+        self
+    }
+
+    /// Rounds the date time half up to the number of digits `%f` shows, instead of truncating it.
+    /// It carries into seconds, minutes and so on, e.g. `23:59:59.9996` with `%T.%3f` becomes
+    /// `00:00:00.000` of the next day. If there are some `%f`s, the most precise one is used.
+    pub fn round_subseconds(mut self, round: bool) -> Self {
+        self.round_subseconds = round;
+        self
+    }
+}
+
 struct FormatCollector<'a, W: Write> {
     date: Date,
     time: Time,
     offset: Option<UtcOffset>,
     zone_name: Option<&'a str>,
+    options: FormatOptions,
+    locale: &'a dyn Locale,
+    /// Locale-provided formats being expanded.
+    expanding: Expanding,
+    /// Case conversion flags given to the locale-provided formats being expanded.
+    case: Spec,
     write: &'a mut W,
 }
 impl<'a, W: Write> FormatCollector<'a, W> {
@@ -31,6 +87,10 @@ impl<'a, W: Write> FormatCollector<'a, W> {
             time: date_time.time(),
             offset: None,
             zone_name: None,
+            options: FormatOptions::default(),
+            locale: &PosixLocale,
+            expanding: Expanding::default(),
+            case: Spec::default(),
             write,
         }
     }
@@ -40,6 +100,10 @@ impl<'a, W: Write> FormatCollector<'a, W> {
             time: date_time.time(),
             offset: Some(date_time.offset()),
             zone_name: None,
+            options: FormatOptions::default(),
+            locale: &PosixLocale,
+            expanding: Expanding::default(),
+            case: Spec::default(),
             write,
         }
     }
@@ -55,6 +119,10 @@ impl<'a, W: Write> FormatCollector<'a, W> {
             time: date_time.time(),
             offset: Some(offset),
             zone_name: Some(zone_name),
+            options: FormatOptions::default(),
+            locale: &PosixLocale,
+            expanding: Expanding::default(),
+            case: Spec::default(),
             write,
         }
     }
@@ -69,9 +137,270 @@ impl<'a, W: Write> FormatCollector<'a, W> {
             time: date_time.time(),
             offset: Some(date_time.offset()),
             zone_name: Some(zone_name),
+            options: FormatOptions::default(),
+            locale: &PosixLocale,
+            expanding: Expanding::default(),
+            case: Spec::default(),
             write,
         }
     }
+
+    #[inline]
+    fn with_options(self, options: FormatOptions) -> Self {
+        Self { options, ..self }
+    }
+
+    /// Sets the options, and rounds the date time to the most precise `%f` of `fmt` if they say
+    /// so, as it needs to be known before writing anything.
+    fn with_options_for(self, fmt: &str, options: FormatOptions) -> Result<Self, FormatError> {
+        let collector = self.with_options(options);
+        if !options.round_subseconds {
+            return Ok(collector);
+        }
+        let digits = StrftimeFormat::new_with_locale(fmt, collector.locale)?.subsecond_digits();
+        Ok(collector.rounded(digits))
+    }
+
+    #[inline]
+    fn with_locale(self, locale: &'a dyn Locale) -> Self {
+        Self { locale, ..self }
+    }
+
+    /// Rounds the date time to `digits` fractional digits if the options say so.
+    /// Keeps it as is if it'd overflow.
+    fn rounded(self, digits: Option<u32>) -> Self {
+        let digits = match digits {
+            Some(digits) if self.options.round_subseconds => digits,
+            _ => return self,
+        };
+        let unit = 10u32.pow(9 - digits);
+        let nanosecond = self.time.nanosecond();
+        let remainder = nanosecond % unit;
+        if remainder == 0 {
+            return self;
+        }
+        let round_up = remainder >= unit / 2;
+        let (h, m, s) = self.time.as_hms();
+        let truncated = Time::from_hms_nano(h, m, s, nanosecond - remainder)
+            .expect("nanosecond should be still in range");
+        let date_time = PrimitiveDateTime::new(self.date, truncated);
+        let date_time = if round_up {
+            match date_time.checked_add(Duration::nanoseconds(unit.into())) {
+                Some(date_time) => date_time,
+                None => return self,
+            }
+        } else {
+            date_time
+        };
+        Self {
+            date: date_time.date(),
+            time: date_time.time(),
+            ..self
+        }
+    }
+}
+
+/// The number of digits `%f` should have.
+#[inline]
+fn subsecond_digits(spec: Spec) -> Result<u32, FormatError> {
+    match spec.width {
+        None => Ok(9),
+        Some(width @ 1..=9) => Ok(width as u32),
+        Some(width) => Err(FormatError::UnsupportedWidth('f', width)),
+    }
+}
+
+/// The number of fractional digits `%s` should have.
+#[inline]
+fn timestamp_precision(spec: Spec) -> Result<u32, FormatError> {
+    match spec.precision {
+        None => Ok(0),
+        Some(precision @ 0..=9) => Ok(precision as u32),
+        Some(precision) => Err(FormatError::UnsupportedPrecision('s', precision)),
+    }
+}
+
+impl<'a, W: Write> FormatCollector<'a, W> {
+    #[inline]
+    fn write_padding(&mut self, c: char, n: usize) -> Result<(), FormatError> {
+        for _ in 0..n {
+            self.write.write_char(c)?;
+        }
+        Ok(())
+    }
+
+    /// Writes a number padded to `digits` digits unless `spec` says something else.
+    /// `+` flag is respected only if `signed` is true, otherwise it's treated as `0` flag.
+    fn number(
+        &mut self,
+        spec: Spec,
+        value: i128,
+        digits: usize,
+        default_padding: Padding,
+        signed: bool,
+    ) -> Result<(), FormatError> {
+        let alt_digits = match self.alt_digits(spec) {
+            Some(AltDigits::Table(table)) => {
+                if let Some(s) = usize::try_from(value).ok().and_then(|v| table.get(v)) {
+                    self.write.write_str(s)?;
+                    return Ok(());
+                }
+                None
+            }
+            Some(AltDigits::Positional(alt_digits)) => Some(alt_digits),
+            None => None,
+        };
+        let zero = alt_digits.map_or('0', |alt_digits| alt_digits[0]);
+        let padding = spec.padding.unwrap_or(default_padding);
+        let width = spec.width.unwrap_or(digits);
+        let abs = value.unsigned_abs();
+        let mut sign = if value < 0 {
+            Some('-')
+        } else if signed
+            && padding == Padding::ZeroWithSign
+            && (abs >= 10u128.pow(digits as u32) || digits < width)
+        {
+            Some('+')
+        } else {
+            None
+        };
+        let len = abs.checked_ilog10().unwrap_or(0) as usize + 1 + sign.is_some() as usize;
+        let shortage = width.saturating_sub(len);
+        match padding {
+            Padding::None => {}
+            Padding::Space => self.write_padding(' ', shortage)?,
+            Padding::Zero | Padding::ZeroWithSign => {
+                if let Some(sign) = sign.take() {
+                    self.write.write_char(sign)?;
+                }
+                self.write_padding(zero, shortage)?;
+            }
+        }
+        if let Some(sign) = sign {
+            self.write.write_char(sign)?;
+        }
+        self.write_digits(alt_digits, format_args!("{}", abs))
+    }
+
+    /// Writes `args`, replacing ASCII digits with `alt_digits` if given.
+    #[inline]
+    fn write_digits(
+        &mut self,
+        alt_digits: Option<[char; 10]>,
+        args: std::fmt::Arguments,
+    ) -> Result<(), FormatError> {
+        match alt_digits {
+            Some(alt_digits) => AltDigitsWriter {
+                inner: &mut *self.write,
+                alt_digits,
+            }
+            .write_fmt(args)?,
+            None => self.write.write_fmt(args)?,
+        }
+        Ok(())
+    }
+
+    /// Writes a string padded with spaces if `spec` says so.
+    /// `swap_to_upper` specifies whether the `#` flag makes it upper case or lower case.
+    fn string(&mut self, spec: Spec, s: &str, swap_to_upper: bool) -> Result<(), FormatError> {
+        let spec = Spec {
+            upper: spec.upper || self.case.upper,
+            swap_case: spec.swap_case || self.case.swap_case,
+            ..spec
+        };
+        let upper = spec.upper || (spec.swap_case && swap_to_upper);
+        let lower = spec.swap_case && !swap_to_upper;
+        if let Some(width) = spec.width {
+            let len = if upper {
+                s.chars().flat_map(char::to_uppercase).count()
+            } else if lower {
+                s.chars().flat_map(char::to_lowercase).count()
+            } else {
+                s.chars().count()
+            };
+            let shortage = width.saturating_sub(len);
+            match spec.padding {
+                Some(Padding::None) => {}
+                None | Some(Padding::Space) => self.write_padding(' ', shortage)?,
+                Some(Padding::Zero | Padding::ZeroWithSign) => self.write_padding('0', shortage)?,
+            }
+        }
+        if upper {
+            for c in s.chars().flat_map(char::to_uppercase) {
+                self.write.write_char(c)?;
+            }
+        } else if lower {
+            for c in s.chars().flat_map(char::to_lowercase) {
+                self.write.write_char(c)?;
+            }
+        } else {
+            self.write.write_str(s)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, W: Write> FormatCollector<'a, W> {
+    /// Formats with the locale-provided `format`, passing the case conversion flags down.
+    fn expand(&mut self, format: LocaleFormat, spec: Spec) -> Result<(), FormatError> {
+        let expanding = self.expanding.enter(format);
+        self.expand_with(expanding, format.get(self.locale), spec)
+    }
+
+    /// Formats with `format`, marking the formats in `expanding` as being expanded.
+    fn expand_with(
+        &mut self,
+        expanding: Option<Expanding>,
+        format: &str,
+        spec: Spec,
+    ) -> Result<(), FormatError> {
+        let expanding = expanding.ok_or(FormatError::RecursiveLocaleFormat)?;
+        let saved = (self.expanding, self.case);
+        self.expanding = expanding;
+        self.case = Spec {
+            upper: self.case.upper || spec.upper,
+            swap_case: self.case.swap_case || spec.swap_case,
+            ..Spec::default()
+        };
+        let result = spec_parser::collect(format, self);
+        (self.expanding, self.case) = saved;
+        result
+    }
+
+    /// The alternative digits, if `%O` is given and the locale has them.
+    #[inline]
+    fn alt_digits(&self, spec: Spec) -> Option<AltDigits<'a>> {
+        if spec.modifier == Some(Modifier::AlternativeDigits) {
+            self.locale.alt_digits()
+        } else {
+            None
+        }
+    }
+
+    /// The era the date is in, if `%E` is given and the locale has one.
+    #[inline]
+    fn era(&self, spec: Spec) -> Option<&'a Era<'a>> {
+        if spec.modifier == Some(Modifier::Era) {
+            Era::find(self.locale.eras(), self.date)
+        } else {
+            None
+        }
+    }
+
+    /// The year, the month and the day of the date in the calendar of the locale.
+    #[inline]
+    fn calendar_date(&self) -> (i32, Month, u8) {
+        self.locale.calendar().calendar_date(self.date)
+    }
+
+    #[inline]
+    fn am_pm(&self) -> &'a str {
+        if self.time.hour() < 12 {
+            self.locale.am()
+        } else {
+            self.locale.pm()
+        }
+    }
 }
 
 impl<'a, W: Write> Collector for FormatCollector<'a, W> {
@@ -79,204 +408,263 @@ impl<'a, W: Write> Collector for FormatCollector<'a, W> {
     type Error = FormatError;
 
     #[inline]
-    fn day_of_week_name_short(&mut self) -> Result<(), Self::Error> {
-        self.write
-            .write_str(util::weekday_short_str(self.date.weekday()))?;
-        Ok(())
+    fn day_of_week_name_short(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let locale = self.locale;
+        self.string(spec, locale.weekday_short(self.date.weekday()), true)
     }
 
     #[inline]
-    fn day_of_week_name_long(&mut self) -> Result<(), Self::Error> {
-        self.write
-            .write_str(util::weekday_long_str(self.date.weekday()))?;
-        Ok(())
+    fn day_of_week_name_long(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let locale = self.locale;
+        self.string(spec, locale.weekday_long(self.date.weekday()), true)
     }
 
     #[inline]
-    fn month_name_short(&mut self) -> Result<(), Self::Error> {
-        self.write
-            .write_str(util::month_short_str(self.date.month()))?;
-        Ok(())
+    fn month_name_short(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let locale = self.locale;
+        let (_, month, _) = self.calendar_date();
+        let name = locale.calendar().month_short(month);
+        self.string(
+            spec,
+            name.unwrap_or_else(|| locale.month_short(month)),
+            true,
+        )
     }
 
     #[inline]
-    fn month_name_long(&mut self) -> Result<(), Self::Error> {
-        self.write
-            .write_str(util::month_long_str(self.date.month()))?;
-        Ok(())
+    fn month_name_long(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let locale = self.locale;
+        let (_, month, _) = self.calendar_date();
+        let name = locale.calendar().month_long(month);
+        self.string(spec, name.unwrap_or_else(|| locale.month_long(month)), true)
     }
 
     #[inline]
-    fn year_prefix(&mut self) -> Result<(), Self::Error> {
-        self.write
-            .write_fmt(format_args!("{:02}", self.date.year().div_euclid(100)))?;
-        Ok(())
+    fn preferred_date_time(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let era = spec.modifier == Some(Modifier::Era);
+        self.expand(LocaleFormat::date_time(era), spec)
     }
 
     #[inline]
-    fn day_of_month(&mut self) -> Result<(), Self::Error> {
-        self.write
-            .write_fmt(format_args!("{:02}", self.date.day()))?;
-        Ok(())
+    fn year_prefix(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        if let Some(era) = self.era(spec) {
+            return self.string(spec, era.name, true);
+        }
+        let (year, _, _) = self.calendar_date();
+        let prefix = year.div_euclid(100);
+        self.number(spec, prefix.into(), 2, Padding::Zero, true)
     }
 
     #[inline]
-    fn day_of_month_blank(&mut self) -> Result<(), Self::Error> {
-        self.write
-            .write_fmt(format_args!("{:2}", self.date.day()))?;
-        Ok(())
+    fn day_of_month(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let (_, _, day) = self.calendar_date();
+        self.number(spec, day.into(), 2, Padding::Zero, false)
     }
 
     #[inline]
-    fn iso8601_week_based_year_suffix(&mut self) -> Result<(), Self::Error> {
+    fn day_of_month_blank(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let (_, _, day) = self.calendar_date();
+        self.number(spec, day.into(), 2, Padding::Space, false)
+    }
+
+    #[inline]
+    fn iso8601_week_based_year_suffix(&mut self, spec: Spec) -> Result<(), Self::Error> {
         let (year, _, _) = self.date.to_iso_week_date();
-        self.write
-            .write_fmt(format_args!("{:02}", year.rem_euclid(100)))?;
-        Ok(())
+        self.number(spec, year.rem_euclid(100).into(), 2, Padding::Zero, false)
     }
 
     #[inline]
-    fn iso8601_week_based_year(&mut self) -> Result<(), Self::Error> {
+    fn iso8601_week_based_year(&mut self, spec: Spec) -> Result<(), Self::Error> {
         let (year, _, _) = self.date.to_iso_week_date();
-        self.write.write_fmt(format_args!("{:4}", year))?;
-        Ok(())
+        self.number(spec, year.into(), 4, Padding::Zero, true)
     }
 
     #[inline]
-    fn hour_of_day(&mut self) -> Result<(), Self::Error> {
-        self.write
-            .write_fmt(format_args!("{:02}", self.time.hour()))?;
-        Ok(())
+    fn hour_of_day(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.number(spec, self.time.hour().into(), 2, Padding::Zero, false)
     }
 
     #[inline]
-    fn hour_of_day_12(&mut self) -> Result<(), Self::Error> {
-        self.write
-            .write_fmt(format_args!("{:02}", (self.time.hour() + 11) % 12 + 1))?;
-        Ok(())
+    fn hour_of_day_12(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let hour = (self.time.hour() + 11) % 12 + 1;
+        self.number(spec, hour.into(), 2, Padding::Zero, false)
     }
 
     #[inline]
-    fn day_of_year(&mut self) -> Result<(), Self::Error> {
-        self.write
-            .write_fmt(format_args!("{:03}", self.date.ordinal()))?;
-        Ok(())
+    fn day_of_year(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let day = self.locale.calendar().ordinal(self.date);
+        self.number(spec, day.into(), 3, Padding::Zero, false)
     }
 
     #[inline]
-    fn hour_of_day_blank(&mut self) -> Result<(), Self::Error> {
-        self.write
-            .write_fmt(format_args!("{:2}", self.time.hour()))?;
-        Ok(())
+    fn hour_of_day_blank(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.number(spec, self.time.hour().into(), 2, Padding::Space, false)
     }
 
     #[inline]
-    fn hour_of_day_12_blank(&mut self) -> Result<(), Self::Error> {
-        self.write
-            .write_fmt(format_args!("{:2}", (self.time.hour() + 11) % 12 + 1))?;
-        Ok(())
+    fn hour_of_day_12_blank(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let hour = (self.time.hour() + 11) % 12 + 1;
+        self.number(spec, hour.into(), 2, Padding::Space, false)
     }
 
     #[inline]
-    fn month_of_year(&mut self) -> Result<(), Self::Error> {
-        self.write
-            .write_fmt(format_args!("{:02}", self.date.month() as u8))?;
-        Ok(())
+    fn month_of_year(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let (_, month, _) = self.calendar_date();
+        self.number(spec, (month as u8).into(), 2, Padding::Zero, false)
     }
 
     #[inline]
-    fn minute_of_hour(&mut self) -> Result<(), Self::Error> {
-        self.write
-            .write_fmt(format_args!("{:02}", self.time.minute()))?;
-        Ok(())
+    fn minute_of_hour(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.number(spec, self.time.minute().into(), 2, Padding::Zero, false)
     }
 
     #[inline]
-    fn ampm(&mut self) -> Result<(), Self::Error> {
-        self.write.write_str(util::ampm_upper(self.time.hour()))?;
-        Ok(())
+    fn ampm(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.string(spec, self.am_pm(), false)
     }
 
     #[inline]
-    fn ampm_lower(&mut self) -> Result<(), Self::Error> {
-        self.write.write_str(util::ampm_lower(self.time.hour()))?;
-        Ok(())
+    fn ampm_lower(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        // Lower case unless `^` is given.
+        let spec = Spec {
+            swap_case: true,
+            ..spec
+        };
+        self.string(spec, self.am_pm(), false)
     }
 
     #[inline]
-    fn second_of_minute(&mut self) -> Result<(), Self::Error> {
-        self.write
-            .write_fmt(format_args!("{:02}", self.time.second()))?;
-        Ok(())
+    fn time_ampm(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.expand(LocaleFormat::TimeAmPm, spec)
     }
 
     #[inline]
-    fn nanosecond_of_second(&mut self) -> Result<(), Self::Error> {
-        self.write
-            .write_fmt(format_args!("{:0>9}", self.time.nanosecond()))?;
-        Ok(())
+    fn seconds_since_epoch(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let precision = timestamp_precision(spec)?;
+        let offset = match self.offset {
+            Some(offset) => offset,
+            None if self.options.require_offset_for_timestamp => {
+                return Err(Self::Error::MissingOffset)
+            }
+            None => UtcOffset::UTC,
+        };
+        let nanos = PrimitiveDateTime::new(self.date, self.time)
+            .assume_offset(offset)
+            .unix_timestamp_nanos();
+        // Round towards negative infinity, like `unix_timestamp()` does.
+        let value = nanos.div_euclid(10i128.pow(9 - precision));
+        self.number(spec, value, 1, Padding::Zero, false)
     }
 
     #[inline]
-    fn day_of_week_from_monday_as_1(&mut self) -> Result<(), Self::Error> {
-        self.write
-            .write_fmt(format_args!("{}", self.date.weekday().number_from_monday()))?;
-        Ok(())
+    fn second_of_minute(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.number(spec, self.time.second().into(), 2, Padding::Zero, false)
     }
 
     #[inline]
-    fn week_number_of_current_year_start_sunday(&mut self) -> Result<(), Self::Error> {
-        self.write
-            .write_fmt(format_args!("{:02}", self.date.sunday_based_week()))?;
-        Ok(())
+    fn nanosecond_of_second(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let digits = subsecond_digits(spec)? as usize;
+        let mut buf = [b'0'; 9];
+        let mut value = self.time.nanosecond();
+        for b in buf.iter_mut().rev() {
+            *b += (value % 10) as u8;
+            value /= 10;
+        }
+        let mut s = &buf[..digits];
+        if spec.padding == Some(Padding::None) {
+            // Trim trailing zeros, but keep at least one digit.
+            while s.len() > 1 && s.last() == Some(&b'0') {
+                s = &s[..s.len() - 1];
+            }
+        }
+        let s = std::str::from_utf8(s).expect("digits should be ASCII");
+        let alt_digits = match self.alt_digits(spec) {
+            Some(AltDigits::Positional(alt_digits)) => Some(alt_digits),
+            // Fractions are not numbers from 0.
+            Some(AltDigits::Table(_)) | None => None,
+        };
+        self.write_digits(alt_digits, format_args!("{}", s))
     }
 
     #[inline]
-    fn iso8601_week_number(&mut self) -> Result<(), Self::Error> {
-        self.write
-            .write_fmt(format_args!("{:02}", self.date.iso_week()))?;
-        Ok(())
+    fn day_of_week_from_monday_as_1(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let weekday = self.date.weekday().number_from_monday();
+        self.number(spec, weekday.into(), 1, Padding::Zero, false)
     }
 
     #[inline]
-    fn day_of_week_from_sunday_as_0(&mut self) -> Result<(), Self::Error> {
-        self.write.write_fmt(format_args!(
-            "{}",
-            self.date.weekday().number_days_from_sunday()
-        ))?;
-        Ok(())
+    fn week_number_of_current_year_start_sunday(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let week = self.date.sunday_based_week();
+        self.number(spec, week.into(), 2, Padding::Zero, false)
     }
 
     #[inline]
-    fn week_number_of_current_year_start_monday(&mut self) -> Result<(), Self::Error> {
-        self.write
-            .write_fmt(format_args!("{:02}", self.date.monday_based_week()))?;
-        Ok(())
+    fn iso8601_week_number(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.number(spec, self.date.iso_week().into(), 2, Padding::Zero, false)
     }
 
     #[inline]
-    fn year_suffix(&mut self) -> Result<(), Self::Error> {
-        let year = self.date.year();
-        self.write
-            .write_fmt(format_args!("{:02}", year.abs() % 100))?;
-        Ok(())
+    fn day_of_week_from_sunday_as_0(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let weekday = self.date.weekday().number_days_from_sunday();
+        self.number(spec, weekday.into(), 1, Padding::Zero, false)
     }
 
     #[inline]
-    fn year(&mut self) -> Result<(), Self::Error> {
-        self.write
-            .write_fmt(format_args!("{:04}", self.date.year()))?;
-        Ok(())
+    fn week_number_of_current_year_start_monday(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let week = self.date.monday_based_week();
+        self.number(spec, week.into(), 2, Padding::Zero, false)
+    }
+
+    #[inline]
+    fn preferred_date(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let era = spec.modifier == Some(Modifier::Era);
+        self.expand(LocaleFormat::date(era), spec)
+    }
+
+    #[inline]
+    fn preferred_time_of_day(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let era = spec.modifier == Some(Modifier::Era);
+        self.expand(LocaleFormat::time(era), spec)
+    }
+
+    #[inline]
+    fn year_suffix(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        if let Some(era) = self.era(spec) {
+            let year = era.year_of(self.date, self.locale.calendar());
+            return self.number(spec, year.into(), 1, Padding::Zero, false);
+        }
+        let (year, _, _) = self.calendar_date();
+        self.number(spec, (year.abs() % 100).into(), 2, Padding::Zero, false)
+    }
+
+    #[inline]
+    fn year(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        if let Some(era) = self.era(spec) {
+            let expanding = self.expanding.enter_era_year();
+            return self.expand_with(expanding, era.format, spec);
+        }
+        let (year, _, _) = self.calendar_date();
+        self.number(spec, year.into(), 4, Padding::Zero, true)
     }
 
     #[inline]
-    fn timezone(&mut self) -> Result<(), Self::Error> {
+    fn timezone(&mut self, spec: Spec) -> Result<(), Self::Error> {
         if let Some(offset) = self.offset {
-            let (h, m, _) = offset.as_hms();
-            if offset.is_negative() {
-                self.write.write_fmt(format_args!("-{:02}{:02}", -h, -m))?;
-            } else {
-                self.write.write_fmt(format_args!("+{:02}{:02}", h, m))?;
+            let (h, m, s) = offset.as_hms();
+            let (h, m, s) = (h.unsigned_abs(), m.unsigned_abs(), s.unsigned_abs());
+            self.write
+                .write_char(if offset.is_negative() { '-' } else { '+' })?;
+            match spec.colons {
+                0 if s == 0 => self.write.write_fmt(format_args!("{:02}{:02}", h, m))?,
+                0 => self
+                    .write
+                    .write_fmt(format_args!("{:02}{:02}{:02}", h, m, s))?,
+                1 if s == 0 => self.write.write_fmt(format_args!("{:02}:{:02}", h, m))?,
+                3 if s == 0 && m == 0 => self.write.write_fmt(format_args!("{:02}", h))?,
+                3 if s == 0 => self.write.write_fmt(format_args!("{:02}:{:02}", h, m))?,
+                _ => self
+                    .write
+                    .write_fmt(format_args!("{:02}:{:02}:{:02}", h, m, s))?,
             }
         }
         // No bytes if no timezone is determinable.
@@ -284,9 +672,9 @@ impl<'a, W: Write> Collector for FormatCollector<'a, W> {
     }
 
     #[inline]
-    fn timezone_name(&mut self) -> Result<(), Self::Error> {
-        if let Some(zone_name) = &self.zone_name {
-            self.write.write_str(zone_name)?;
+    fn timezone_name(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        if let Some(zone_name) = self.zone_name {
+            self.string(spec, zone_name, false)?;
         }
         // No bytes if no timezone information exists.
         Ok(())
@@ -313,16 +701,72 @@ impl<'a, W: Write> Collector for FormatCollector<'a, W> {
         Err(Self::Error::UnknownSpecifier(specifier))
     }
 
+    #[inline]
+    fn too_wide(&mut self, specifier: char, width: usize) -> Result<(), Self::Error> {
+        Err(Self::Error::UnsupportedWidth(specifier, width))
+    }
+
     #[inline]
     fn output(self) -> Result<Self::Output, Self::Error> {
         Ok(())
     }
 }
 
+/// Replaces ASCII digits with alternative ones while writing.
+struct AltDigitsWriter<'a, W: Write> {
+    inner: &'a mut W,
+    alt_digits: [char; 10],
+}
+
+impl<'a, W: Write> Write for AltDigitsWriter<'a, W> {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        for c in s.chars() {
+            match c.to_digit(10) {
+                Some(d) => self.inner.write_char(self.alt_digits[d as usize])?,
+                None => self.inner.write_char(c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Adapts `std::io::Write` to `std::fmt::Write`, keeping the I/O error that `fmt::Error` can't
+/// carry.
+struct IoWriter<'a, W: io::Write> {
+    inner: &'a mut W,
+    error: Option<io::Error>,
+}
+
+impl<'a, W: io::Write> Write for IoWriter<'a, W> {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|e| {
+            self.error = Some(e);
+            std::fmt::Error
+        })
+    }
+}
+
+/// Runs `f` against `write` adapted to `std::fmt::Write`.
+/// I/O errors are returned as is, and `FormatError`s are wrapped as `io::ErrorKind::InvalidInput`.
+fn with_io_writer<W: io::Write>(
+    write: &mut W,
+    f: impl FnOnce(&mut IoWriter<'_, W>) -> Result<(), FormatError>,
+) -> io::Result<()> {
+    let mut writer = IoWriter {
+        inner: write,
+        error: None,
+    };
+    let result = f(&mut writer);
+    if let Some(e) = writer.error {
+        return Err(e);
+    }
+    result.map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+}
+
 pub fn format_date_time(fmt: &str, date_time: PrimitiveDateTime) -> Result<String, FormatError> {
     let mut ret = String::new();
-    let collector = FormatCollector::from_date_time(date_time, &mut ret);
-    spec_parser::parse_conversion_specifications(fmt, collector)?;
+    format_date_time_into(&mut ret, fmt, date_time)?;
     Ok(ret)
 }
 
@@ -331,8 +775,7 @@ pub fn format_offset_date_time(
     date_time: OffsetDateTime,
 ) -> Result<String, FormatError> {
     let mut ret = String::new();
-    let collector = FormatCollector::from_offset_date_time(date_time, &mut ret);
-    spec_parser::parse_conversion_specifications(fmt, collector)?;
+    format_offset_date_time_into(&mut ret, fmt, date_time)?;
     Ok(ret)
 }
 
@@ -343,8 +786,7 @@ pub fn format_zoned_date_time(
     zone_name: &str,
 ) -> Result<String, FormatError> {
     let mut ret = String::new();
-    let collector = FormatCollector::from_zoned_date_time(date_time, offset, zone_name, &mut ret);
-    spec_parser::parse_conversion_specifications(fmt, collector)?;
+    format_zoned_date_time_into(&mut ret, fmt, date_time, offset, zone_name)?;
     Ok(ret)
 }
 
@@ -354,11 +796,147 @@ pub fn format_zoned_offset_date_time(
     zone_name: &str,
 ) -> Result<String, FormatError> {
     let mut ret = String::new();
-    let collector = FormatCollector::from_zoned_offset_date_time(date_time, zone_name, &mut ret);
+    format_zoned_offset_date_time_into(&mut ret, fmt, date_time, zone_name)?;
+    Ok(ret)
+}
+
+pub fn format_date_time_with_locale(
+    fmt: &str,
+    date_time: PrimitiveDateTime,
+    locale: &dyn Locale,
+    options: &FormatOptions,
+) -> Result<String, FormatError> {
+    let mut ret = String::new();
+    let collector = FormatCollector::from_date_time(date_time, &mut ret)
+        .with_locale(locale)
+        .with_options_for(fmt, *options)?;
     spec_parser::parse_conversion_specifications(fmt, collector)?;
     Ok(ret)
 }
 
+pub fn format_offset_date_time_with_locale(
+    fmt: &str,
+    date_time: OffsetDateTime,
+    locale: &dyn Locale,
+    options: &FormatOptions,
+) -> Result<String, FormatError> {
+    let mut ret = String::new();
+    let collector = FormatCollector::from_offset_date_time(date_time, &mut ret)
+        .with_locale(locale)
+        .with_options_for(fmt, *options)?;
+    spec_parser::parse_conversion_specifications(fmt, collector)?;
+    Ok(ret)
+}
+
+pub fn format_zoned_date_time_with_locale(
+    fmt: &str,
+    date_time: PrimitiveDateTime,
+    offset: UtcOffset,
+    zone_name: &str,
+    locale: &dyn Locale,
+    options: &FormatOptions,
+) -> Result<String, FormatError> {
+    let mut ret = String::new();
+    let collector = FormatCollector::from_zoned_date_time(date_time, offset, zone_name, &mut ret)
+        .with_locale(locale)
+        .with_options_for(fmt, *options)?;
+    spec_parser::parse_conversion_specifications(fmt, collector)?;
+    Ok(ret)
+}
+
+pub fn format_zoned_offset_date_time_with_locale(
+    fmt: &str,
+    date_time: OffsetDateTime,
+    zone_name: &str,
+    locale: &dyn Locale,
+    options: &FormatOptions,
+) -> Result<String, FormatError> {
+    let mut ret = String::new();
+    let collector = FormatCollector::from_zoned_offset_date_time(date_time, zone_name, &mut ret)
+        .with_locale(locale)
+        .with_options_for(fmt, *options)?;
+    spec_parser::parse_conversion_specifications(fmt, collector)?;
+    Ok(ret)
+}
+
+pub fn format_date_time_into(
+    write: &mut impl Write,
+    fmt: &str,
+    date_time: PrimitiveDateTime,
+) -> Result<(), FormatError> {
+    let collector = FormatCollector::from_date_time(date_time, write);
+    spec_parser::parse_conversion_specifications(fmt, collector)
+}
+
+pub fn format_offset_date_time_into(
+    write: &mut impl Write,
+    fmt: &str,
+    date_time: OffsetDateTime,
+) -> Result<(), FormatError> {
+    let collector = FormatCollector::from_offset_date_time(date_time, write);
+    spec_parser::parse_conversion_specifications(fmt, collector)
+}
+
+pub fn format_zoned_date_time_into(
+    write: &mut impl Write,
+    fmt: &str,
+    date_time: PrimitiveDateTime,
+    offset: UtcOffset,
+    zone_name: &str,
+) -> Result<(), FormatError> {
+    let collector = FormatCollector::from_zoned_date_time(date_time, offset, zone_name, write);
+    spec_parser::parse_conversion_specifications(fmt, collector)
+}
+
+pub fn format_zoned_offset_date_time_into(
+    write: &mut impl Write,
+    fmt: &str,
+    date_time: OffsetDateTime,
+    zone_name: &str,
+) -> Result<(), FormatError> {
+    let collector = FormatCollector::from_zoned_offset_date_time(date_time, zone_name, write);
+    spec_parser::parse_conversion_specifications(fmt, collector)
+}
+
+pub fn format_date_time_into_io(
+    write: &mut impl io::Write,
+    fmt: &str,
+    date_time: PrimitiveDateTime,
+) -> io::Result<()> {
+    with_io_writer(write, |w| format_date_time_into(w, fmt, date_time))
+}
+
+pub fn format_offset_date_time_into_io(
+    write: &mut impl io::Write,
+    fmt: &str,
+    date_time: OffsetDateTime,
+) -> io::Result<()> {
+    with_io_writer(write, |w| format_offset_date_time_into(w, fmt, date_time))
+}
+
+pub fn format_zoned_date_time_into_io(
+    write: &mut impl io::Write,
+    fmt: &str,
+    date_time: PrimitiveDateTime,
+    offset: UtcOffset,
+    zone_name: &str,
+) -> io::Result<()> {
+    with_io_writer(write, |w| {
+        format_zoned_date_time_into(w, fmt, date_time, offset, zone_name)
+    })
+}
+
+pub fn format_zoned_offset_date_time_into_io(
+    write: &mut impl io::Write,
+    fmt: &str,
+    date_time: OffsetDateTime,
+    zone_name: &str,
+) -> io::Result<()> {
+    with_io_writer(write, |w| {
+        format_zoned_offset_date_time_into(w, fmt, date_time, zone_name)
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::{format_date_time, format_offset_date_time};
@@ -494,6 +1072,196 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_flags_and_width() -> Result<(), super::FormatError> {
+        let dt = datetime!(2022-03-06 02:04:06);
+        assert_eq!(format_date_time("%-d %-m %-H %-j", dt)?, "6 3 2 65");
+        assert_eq!(format_date_time("%_d %_H %_j", dt)?, " 6  2  65");
+        assert_eq!(format_date_time("%0e %0k %-e", dt)?, "06 02 6");
+        assert_eq!(format_date_time("%3d %_3d %-3d", dt)?, "006   6 6");
+        assert_eq!(
+            format_date_time("%010Y %_6Y %-6Y", dt)?,
+            "0000002022   2022 2022"
+        );
+        assert_eq!(format_date_time("%+4Y %+6Y %+3C", dt)?, "2022 +02022 +20");
+        assert_eq!(
+            format_date_time("%+Y %Y", datetime!(+12345-01-01 00:00:00))?,
+            "+12345 12345"
+        );
+        assert_eq!(
+            format_date_time("%05Y %_5Y %-5Y", datetime!(-1-01-01 00:00:00))?,
+            "-0001    -1 -1"
+        );
+        assert_eq!(
+            format_date_time("%^a %^A %^b %^B", dt)?,
+            "SUN SUNDAY MAR MARCH"
+        );
+        assert_eq!(format_date_time("%#a %#B %#p %^P", dt)?, "SUN MARCH am AM");
+        assert_eq!(
+            format_date_time("%10A|%-10A|%010b", dt)?,
+            "    Sunday|Sunday|0000000Mar"
+        );
+        assert_eq!(format_date_time("%^c", dt)?, "SUN MAR  6 02:04:06 2022");
+        assert_eq!(
+            super::format_zoned_date_time("%Z %#Z %^Z %6Z", dt, offset!(+9:00), "Jst")?,
+            "Jst jst JST    Jst"
+        );
+        assert_eq!(format_date_time("%-", dt)?, "%-");
+        assert_eq!(
+            format_date_time("%-q", dt),
+            Err(super::FormatError::UnknownSpecifier('q'))
+        );
+        assert_eq!(format_date_time("%1024d", dt)?.len(), 1024);
+        assert_eq!(
+            format_date_time("%99999999999d", dt),
+            Err(super::FormatError::UnsupportedWidth('d', 99999999999))
+        );
+        assert_eq!(
+            format_date_time("%99999999999999999999999Z", dt),
+            Err(super::FormatError::UnsupportedWidth('Z', usize::MAX))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_modifiers() -> Result<(), super::FormatError> {
+        let dt = datetime!(2022-03-06 02:04:06);
+        for (modified, unmodified) in [
+            ("%Ec %EC %Ex %EX %Ey %EY", "%c %C %x %X %y %Y"),
+            ("%Od %Oe %OH %OI %Om %OM", "%d %e %H %I %m %M"),
+            ("%OS %Ou %OU %OV %Ow %OW %Oy", "%S %u %U %V %w %W %y"),
+            ("%-Od %_OH %^Ec", "%-d %_H %^c"),
+        ] {
+            assert_eq!(
+                format_date_time(modified, dt)?,
+                format_date_time(unmodified, dt)?
+            );
+        }
+        assert_eq!(format_date_time("%E", dt)?, "%E");
+        assert_eq!(
+            format_date_time("%Eq", dt),
+            Err(super::FormatError::UnknownSpecifier('q'))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_subsecond_digits() -> Result<(), super::FormatError> {
+        let dt = datetime!(2022-03-06 12:34:56.120406);
+        assert_eq!(
+            format_date_time("%f %1f %3f %6f %9f", dt)?,
+            "120406000 1 120 120406 120406000"
+        );
+        assert_eq!(format_date_time("%N %3N %-N", dt)?, "120406000 120 120406");
+        assert_eq!(format_date_time("%-f %-2f %-3f", dt)?, "120406 12 12");
+        assert_eq!(
+            format_date_time("%-f %-3f", datetime!(2022-03-06 12:34:56))?,
+            "0 0"
+        );
+        assert_eq!(
+            format_date_time("%10f", dt),
+            Err(super::FormatError::UnsupportedWidth('f', 10))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_seconds_since_epoch() -> Result<(), super::FormatError> {
+        let dt = datetime!(2022-03-06 12:34:56.789012345);
+        assert_eq!(format_date_time("%s", dt)?, "1646570096");
+        assert_eq!(
+            format_offset_date_time("%s", dt.assume_offset(offset!(+9:00)))?,
+            "1646537696"
+        );
+        assert_eq!(
+            format_date_time("%s.%f %.0s %.3s %.6s %.9s", dt)?,
+            "1646570096.789012345 1646570096 1646570096789 1646570096789012 1646570096789012345"
+        );
+        assert_eq!(
+            format_date_time("%12s %_12s %-12s %12.3s", dt)?,
+            "001646570096   1646570096 1646570096 1646570096789"
+        );
+        let before_epoch = datetime!(1969-12-31 23:59:58.5);
+        assert_eq!(format_date_time("%s.%f", before_epoch)?, "-2.500000000");
+        assert_eq!(format_date_time("%.3s %-s", before_epoch)?, "-1500 -2");
+        assert_eq!(
+            format_date_time("%.10s", dt),
+            Err(super::FormatError::UnsupportedPrecision('s', 10))
+        );
+        assert_eq!(
+            format_date_time("%.3d", dt),
+            Err(super::FormatError::UnknownSpecifier('.'))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_into() -> Result<(), Box<dyn std::error::Error>> {
+        use super::{
+            format_date_time_into, format_date_time_into_io, format_zoned_offset_date_time_into,
+            format_zoned_offset_date_time_into_io,
+        };
+
+        let dt = datetime!(2022-03-06 12:34:56);
+        let mut buf = "prefix ".to_string();
+        format_date_time_into(&mut buf, "%F", dt)?;
+        format_zoned_offset_date_time_into(
+            &mut buf,
+            " %T %z %Z",
+            dt.assume_offset(offset!(+9:00)),
+            "JST",
+        )?;
+        assert_eq!(buf, "prefix 2022-03-06 12:34:56 +0900 JST");
+
+        let mut bytes = Vec::new();
+        format_date_time_into_io(&mut bytes, "%F", dt)?;
+        format_zoned_offset_date_time_into_io(
+            &mut bytes,
+            " %T %z %Z",
+            dt.assume_offset(offset!(+9:00)),
+            "JST",
+        )?;
+        assert_eq!(bytes, b"2022-03-06 12:34:56 +0900 JST");
+
+        let err = format_date_time_into_io(&mut bytes, "%q", dt).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        let err = format_date_time_into_io(&mut &mut [0u8; 4][..], "%F", dt).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::WriteZero);
+        Ok(())
+    }
+
+    #[test]
+    fn test_options() -> Result<(), super::FormatError> {
+        use super::{
+            format_date_time_with_locale, format_zoned_offset_date_time_with_locale, FormatError,
+            FormatOptions,
+        };
+        use crate::locale::PosixLocale;
+
+        let dt = datetime!(2022-12-31 23:59:59.9996);
+        let options = FormatOptions::new().round_subseconds(true);
+        assert_eq!(
+            format_date_time_with_locale("%F %T.%3f", dt, &PosixLocale, &options)?,
+            "2023-01-01 00:00:00.000"
+        );
+        let options = FormatOptions::new().require_offset_for_timestamp(true);
+        assert_eq!(
+            format_date_time_with_locale("%s", dt, &PosixLocale, &options),
+            Err(FormatError::MissingOffset)
+        );
+        assert_eq!(
+            format_zoned_offset_date_time_with_locale(
+                "%s %Z",
+                dt.assume_offset(offset!(+9:00)),
+                "JST",
+                &PosixLocale,
+                &options
+            )?,
+            "1672498799 JST"
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_year_prefix() -> Result<(), super::FormatError> {
         let fmt = "%C";
@@ -531,6 +1299,28 @@ mod tests {
             format_offset_date_time(fmt, datetime!(2022-02-02 01:01:01 -1:23))?,
             "-0123".to_string()
         );
+        let lmt = datetime!(1880-01-01 00:00:00 +0:18:59);
+        let cases = [
+            (offset!(UTC), "+0000 +00:00 +00:00:00 +00"),
+            (offset!(+9:00), "+0900 +09:00 +09:00:00 +09"),
+            (offset!(+5:30), "+0530 +05:30 +05:30:00 +05:30"),
+            (offset!(-0:30), "-0030 -00:30 -00:30:00 -00:30"),
+            (offset!(+0:18:59), "+001859 +00:18:59 +00:18:59 +00:18:59"),
+        ];
+        for (offset, expected) in cases {
+            assert_eq!(
+                format_offset_date_time("%z %:z %::z %:::z", lmt.to_offset(offset))?,
+                expected
+            );
+        }
+        let naive = datetime!(1880-01-01 00:00:00);
+        assert_eq!(format_date_time("%:z", naive)?, "");
+        for fmt in ["%::::z", "%:Y"] {
+            assert_eq!(
+                format_date_time(fmt, naive),
+                Err(super::FormatError::UnknownSpecifier(':'))
+            );
+        }
         Ok(())
     }
 
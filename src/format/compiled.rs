@@ -0,0 +1,715 @@
+use std::{
+    fmt::{self, Write},
+    io,
+    ops::Range,
+};
+
+use time::{OffsetDateTime, PrimitiveDateTime, UtcOffset};
+
+use super::{
+    spec_parser::{self, Collector, Spec},
+    subsecond_digits, timestamp_precision, with_io_writer, FormatCollector, FormatError,
+    FormatOptions,
+};
+use crate::{
+    locale::{Expanding, Locale, LocaleFormat, PosixLocale},
+    util::Modifier,
+};
+
+/// A conversion specifier, i.e. a required method of `Collector`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Conversion {
+    DayOfWeekNameShort,
+    DayOfWeekNameLong,
+    MonthNameShort,
+    MonthNameLong,
+    YearPrefix,
+    DayOfMonth,
+    DayOfMonthBlank,
+    Iso8601WeekBasedYearSuffix,
+    Iso8601WeekBasedYear,
+    HourOfDay,
+    HourOfDay12,
+    DayOfYear,
+    HourOfDayBlank,
+    HourOfDay12Blank,
+    MonthOfYear,
+    MinuteOfHour,
+    Ampm,
+    AmpmLower,
+    SecondsSinceEpoch,
+    SecondOfMinute,
+    NanosecondOfSecond,
+    DayOfWeekFromMondayAs1,
+    WeekNumberOfCurrentYearStartSunday,
+    Iso8601WeekNumber,
+    DayOfWeekFromSundayAs0,
+    WeekNumberOfCurrentYearStartMonday,
+    YearSuffix,
+    Year,
+    Timezone,
+    TimezoneName,
+}
+
+impl Conversion {
+    #[inline]
+    fn apply<C: Collector>(self, collector: &mut C, spec: Spec) -> Result<(), C::Error> {
+        match self {
+            Self::DayOfWeekNameShort => collector.day_of_week_name_short(spec),
+            Self::DayOfWeekNameLong => collector.day_of_week_name_long(spec),
+            Self::MonthNameShort => collector.month_name_short(spec),
+            Self::MonthNameLong => collector.month_name_long(spec),
+            Self::YearPrefix => collector.year_prefix(spec),
+            Self::DayOfMonth => collector.day_of_month(spec),
+            Self::DayOfMonthBlank => collector.day_of_month_blank(spec),
+            Self::Iso8601WeekBasedYearSuffix => collector.iso8601_week_based_year_suffix(spec),
+            Self::Iso8601WeekBasedYear => collector.iso8601_week_based_year(spec),
+            Self::HourOfDay => collector.hour_of_day(spec),
+            Self::HourOfDay12 => collector.hour_of_day_12(spec),
+            Self::DayOfYear => collector.day_of_year(spec),
+            Self::HourOfDayBlank => collector.hour_of_day_blank(spec),
+            Self::HourOfDay12Blank => collector.hour_of_day_12_blank(spec),
+            Self::MonthOfYear => collector.month_of_year(spec),
+            Self::MinuteOfHour => collector.minute_of_hour(spec),
+            Self::Ampm => collector.ampm(spec),
+            Self::AmpmLower => collector.ampm_lower(spec),
+            Self::SecondsSinceEpoch => collector.seconds_since_epoch(spec),
+            Self::SecondOfMinute => collector.second_of_minute(spec),
+            Self::NanosecondOfSecond => collector.nanosecond_of_second(spec),
+            Self::DayOfWeekFromMondayAs1 => collector.day_of_week_from_monday_as_1(spec),
+            Self::WeekNumberOfCurrentYearStartSunday => {
+                collector.week_number_of_current_year_start_sunday(spec)
+            }
+            Self::Iso8601WeekNumber => collector.iso8601_week_number(spec),
+            Self::DayOfWeekFromSundayAs0 => collector.day_of_week_from_sunday_as_0(spec),
+            Self::WeekNumberOfCurrentYearStartMonday => {
+                collector.week_number_of_current_year_start_monday(spec)
+            }
+            Self::YearSuffix => collector.year_suffix(spec),
+            Self::Year => collector.year(spec),
+            Self::Timezone => collector.timezone(spec),
+            Self::TimezoneName => collector.timezone_name(spec),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Item {
+    /// A range of `StrftimeFormat::literals`.
+    Literal(Range<usize>),
+    Conversion(Conversion, Spec),
+}
+
+/// A `strftime`-like conversion specification that is parsed and validated once, so that it can
+/// format many date times without scanning the specification again.
+///
+/// The formats of the locale, like that of `%c`, are compiled in place, and the locale is kept to
+/// look up the names, the eras and the alternative digits while formatting.
+///
+/// ```
+/// use time::macros::datetime;
+/// use time_fmt::format::StrftimeFormat;
+///
+/// let format = StrftimeFormat::new("%Y-%m-%d %H:%M:%S").unwrap();
+/// assert_eq!(
+///     format.format_date_time(datetime!(2022-03-06 12:34:56)).unwrap(),
+///     "2022-03-06 12:34:56"
+/// );
+/// ```
+#[derive(Clone)]
+pub struct StrftimeFormat<'a> {
+    literals: String,
+    items: Vec<Item>,
+    /// The largest number of digits of `%f`s, to round date times to.
+    subsecond_digits: Option<u32>,
+    options: FormatOptions,
+    locale: &'a dyn Locale,
+}
+
+impl fmt::Debug for StrftimeFormat<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StrftimeFormat")
+            .field("literals", &self.literals)
+            .field("items", &self.items)
+            .field("subsecond_digits", &self.subsecond_digits)
+            .field("options", &self.options)
+            .finish_non_exhaustive()
+    }
+}
+
+struct CompileCollector<'a> {
+    literals: String,
+    items: Vec<Item>,
+    subsecond_digits: Option<u32>,
+    locale: &'a dyn Locale,
+    /// Locale-provided formats being expanded.
+    expanding: Expanding,
+    /// The case conversion flags of the formats being expanded, as `FormatCollector::case`.
+    case: Spec,
+}
+
+impl CompileCollector<'_> {
+    /// Compiles the locale-provided `format` in place, as `FormatCollector` expands it.
+    fn expand(&mut self, format: LocaleFormat, spec: Spec) -> Result<(), FormatError> {
+        let expanding = self
+            .expanding
+            .enter(format)
+            .ok_or(FormatError::RecursiveLocaleFormat)?;
+        let saved = (self.expanding, self.case);
+        self.expanding = expanding;
+        self.case = Spec {
+            upper: self.case.upper || spec.upper,
+            swap_case: self.case.swap_case || spec.swap_case,
+            ..Spec::default()
+        };
+        let result = spec_parser::collect(format.get(self.locale), self);
+        (self.expanding, self.case) = saved;
+        result
+    }
+
+    #[inline]
+    fn push(&mut self, conversion: Conversion, spec: Spec) -> Result<(), FormatError> {
+        let spec = Spec {
+            upper: spec.upper || self.case.upper,
+            swap_case: spec.swap_case || self.case.swap_case,
+            ..spec
+        };
+        self.items.push(Item::Conversion(conversion, spec));
+        Ok(())
+    }
+
+    #[inline]
+    fn push_literal(&mut self, lit: &str) -> Result<(), FormatError> {
+        let start = self.literals.len();
+        self.literals.push_str(lit);
+        let end = self.literals.len();
+        // Merge with the previous literal, which is always adjacent in `literals`.
+        if let Some(Item::Literal(range)) = self.items.last_mut() {
+            range.end = end;
+        } else {
+            self.items.push(Item::Literal(start..end));
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Collector for CompileCollector<'a> {
+    type Output = StrftimeFormat<'a>;
+    type Error = FormatError;
+
+    #[inline]
+    fn day_of_week_name_short(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::DayOfWeekNameShort, spec)
+    }
+
+    #[inline]
+    fn day_of_week_name_long(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::DayOfWeekNameLong, spec)
+    }
+
+    #[inline]
+    fn month_name_short(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::MonthNameShort, spec)
+    }
+
+    #[inline]
+    fn month_name_long(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::MonthNameLong, spec)
+    }
+
+    #[inline]
+    fn preferred_date_time(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let era = spec.modifier == Some(Modifier::Era);
+        self.expand(LocaleFormat::date_time(era), spec)
+    }
+
+    #[inline]
+    fn year_prefix(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::YearPrefix, spec)
+    }
+
+    #[inline]
+    fn day_of_month(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::DayOfMonth, spec)
+    }
+
+    #[inline]
+    fn day_of_month_blank(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::DayOfMonthBlank, spec)
+    }
+
+    #[inline]
+    fn iso8601_week_based_year_suffix(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::Iso8601WeekBasedYearSuffix, spec)
+    }
+
+    #[inline]
+    fn iso8601_week_based_year(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::Iso8601WeekBasedYear, spec)
+    }
+
+    #[inline]
+    fn hour_of_day(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::HourOfDay, spec)
+    }
+
+    #[inline]
+    fn hour_of_day_12(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::HourOfDay12, spec)
+    }
+
+    #[inline]
+    fn day_of_year(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::DayOfYear, spec)
+    }
+
+    #[inline]
+    fn hour_of_day_blank(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::HourOfDayBlank, spec)
+    }
+
+    #[inline]
+    fn hour_of_day_12_blank(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::HourOfDay12Blank, spec)
+    }
+
+    #[inline]
+    fn month_of_year(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::MonthOfYear, spec)
+    }
+
+    #[inline]
+    fn minute_of_hour(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::MinuteOfHour, spec)
+    }
+
+    #[inline]
+    fn ampm(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::Ampm, spec)
+    }
+
+    #[inline]
+    fn ampm_lower(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::AmpmLower, spec)
+    }
+
+    #[inline]
+    fn time_ampm(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.expand(LocaleFormat::TimeAmPm, spec)
+    }
+
+    #[inline]
+    fn seconds_since_epoch(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        timestamp_precision(spec)?;
+        self.push(Conversion::SecondsSinceEpoch, spec)
+    }
+
+    #[inline]
+    fn second_of_minute(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::SecondOfMinute, spec)
+    }
+
+    #[inline]
+    fn nanosecond_of_second(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let digits = subsecond_digits(spec)?;
+        self.subsecond_digits = self.subsecond_digits.max(Some(digits));
+        self.push(Conversion::NanosecondOfSecond, spec)
+    }
+
+    #[inline]
+    fn day_of_week_from_monday_as_1(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::DayOfWeekFromMondayAs1, spec)
+    }
+
+    #[inline]
+    fn week_number_of_current_year_start_sunday(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::WeekNumberOfCurrentYearStartSunday, spec)
+    }
+
+    #[inline]
+    fn iso8601_week_number(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::Iso8601WeekNumber, spec)
+    }
+
+    #[inline]
+    fn day_of_week_from_sunday_as_0(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::DayOfWeekFromSundayAs0, spec)
+    }
+
+    #[inline]
+    fn week_number_of_current_year_start_monday(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::WeekNumberOfCurrentYearStartMonday, spec)
+    }
+
+    #[inline]
+    fn preferred_date(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let era = spec.modifier == Some(Modifier::Era);
+        self.expand(LocaleFormat::date(era), spec)
+    }
+
+    #[inline]
+    fn preferred_time_of_day(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let era = spec.modifier == Some(Modifier::Era);
+        self.expand(LocaleFormat::time(era), spec)
+    }
+
+    #[inline]
+    fn year_suffix(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::YearSuffix, spec)
+    }
+
+    #[inline]
+    fn year(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::Year, spec)
+    }
+
+    #[inline]
+    fn timezone(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::Timezone, spec)
+    }
+
+    #[inline]
+    fn timezone_name(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::TimezoneName, spec)
+    }
+
+    #[inline]
+    fn static_str(&mut self, s: &'static str) -> Result<(), Self::Error> {
+        self.push_literal(s)
+    }
+
+    #[inline]
+    fn literal(
+        &mut self,
+        lit: &str,
+        _fmt_span: impl std::slice::SliceIndex<[u8], Output = [u8]>,
+    ) -> Result<(), Self::Error> {
+        self.push_literal(lit)
+    }
+
+    #[inline]
+    fn unknown(&mut self, specifier: char) -> Result<(), Self::Error> {
+        Err(Self::Error::UnknownSpecifier(specifier))
+    }
+
+    #[inline]
+    fn too_wide(&mut self, specifier: char, width: usize) -> Result<(), Self::Error> {
+        Err(Self::Error::UnsupportedWidth(specifier, width))
+    }
+
+    #[inline]
+    fn output(self) -> Result<Self::Output, Self::Error> {
+        Ok(StrftimeFormat {
+            literals: self.literals,
+            items: self.items,
+            subsecond_digits: self.subsecond_digits,
+            options: FormatOptions::default(),
+            locale: self.locale,
+        })
+    }
+}
+
+impl StrftimeFormat<'static> {
+    /// Parses and validates the conversion specification, in the C/POSIX locale.
+    pub fn new(fmt: &str) -> Result<Self, FormatError> {
+        Self::new_with_locale(fmt, &PosixLocale)
+    }
+}
+
+impl<'a> StrftimeFormat<'a> {
+    /// Parses and validates the conversion specification, in `locale`.
+    pub fn new_with_locale(fmt: &str, locale: &'a dyn Locale) -> Result<Self, FormatError> {
+        let collector = CompileCollector {
+            literals: String::new(),
+            items: Vec::new(),
+            subsecond_digits: None,
+            locale,
+            expanding: Expanding::default(),
+            case: Spec::default(),
+        };
+        spec_parser::parse_conversion_specifications(fmt, collector)
+    }
+
+    /// Replaces the options used to format date times.
+    pub fn with_options(self, options: FormatOptions) -> Self {
+        Self { options, ..self }
+    }
+
+    /// Feeds the compiled specification to `collector` as if it were parsed right now.
+    fn replay<C: Collector>(&self, mut collector: C) -> Result<C::Output, C::Error> {
+        for item in &self.items {
+            match item {
+                Item::Literal(range) => {
+                    collector.literal(&self.literals[range.clone()], range.clone())?
+                }
+                Item::Conversion(conversion, spec) => conversion.apply(&mut collector, *spec)?,
+            }
+        }
+        collector.output()
+    }
+
+    /// The largest number of digits of `%f`s in the specification, if any.
+    pub(super) fn subsecond_digits(&self) -> Option<u32> {
+        self.subsecond_digits
+    }
+
+    fn format_with<W: Write>(&self, collector: FormatCollector<'_, W>) -> Result<(), FormatError> {
+        let collector = collector
+            .with_locale(self.locale)
+            .with_options(self.options)
+            .rounded(self.subsecond_digits);
+        self.replay(collector)
+    }
+
+    pub fn format_date_time(&self, date_time: PrimitiveDateTime) -> Result<String, FormatError> {
+        let mut ret = String::new();
+        self.format_date_time_into(&mut ret, date_time)?;
+        Ok(ret)
+    }
+
+    pub fn format_offset_date_time(
+        &self,
+        date_time: OffsetDateTime,
+    ) -> Result<String, FormatError> {
+        let mut ret = String::new();
+        self.format_offset_date_time_into(&mut ret, date_time)?;
+        Ok(ret)
+    }
+
+    pub fn format_zoned_date_time(
+        &self,
+        date_time: PrimitiveDateTime,
+        offset: UtcOffset,
+        zone_name: &str,
+    ) -> Result<String, FormatError> {
+        let mut ret = String::new();
+        self.format_zoned_date_time_into(&mut ret, date_time, offset, zone_name)?;
+        Ok(ret)
+    }
+
+    pub fn format_zoned_offset_date_time(
+        &self,
+        date_time: OffsetDateTime,
+        zone_name: &str,
+    ) -> Result<String, FormatError> {
+        let mut ret = String::new();
+        self.format_zoned_offset_date_time_into(&mut ret, date_time, zone_name)?;
+        Ok(ret)
+    }
+
+    pub fn format_date_time_into(
+        &self,
+        write: &mut impl Write,
+        date_time: PrimitiveDateTime,
+    ) -> Result<(), FormatError> {
+        self.format_with(FormatCollector::from_date_time(date_time, write))
+    }
+
+    pub fn format_offset_date_time_into(
+        &self,
+        write: &mut impl Write,
+        date_time: OffsetDateTime,
+    ) -> Result<(), FormatError> {
+        self.format_with(FormatCollector::from_offset_date_time(date_time, write))
+    }
+
+    pub fn format_zoned_date_time_into(
+        &self,
+        write: &mut impl Write,
+        date_time: PrimitiveDateTime,
+        offset: UtcOffset,
+        zone_name: &str,
+    ) -> Result<(), FormatError> {
+        self.format_with(FormatCollector::from_zoned_date_time(
+            date_time, offset, zone_name, write,
+        ))
+    }
+
+    pub fn format_zoned_offset_date_time_into(
+        &self,
+        write: &mut impl Write,
+        date_time: OffsetDateTime,
+        zone_name: &str,
+    ) -> Result<(), FormatError> {
+        self.format_with(FormatCollector::from_zoned_offset_date_time(
+            date_time, zone_name, write,
+        ))
+    }
+
+    pub fn format_date_time_into_io(
+        &self,
+        write: &mut impl io::Write,
+        date_time: PrimitiveDateTime,
+    ) -> io::Result<()> {
+        with_io_writer(write, |w| self.format_date_time_into(w, date_time))
+    }
+
+    pub fn format_offset_date_time_into_io(
+        &self,
+        write: &mut impl io::Write,
+        date_time: OffsetDateTime,
+    ) -> io::Result<()> {
+        with_io_writer(write, |w| self.format_offset_date_time_into(w, date_time))
+    }
+
+    pub fn format_zoned_date_time_into_io(
+        &self,
+        write: &mut impl io::Write,
+        date_time: PrimitiveDateTime,
+        offset: UtcOffset,
+        zone_name: &str,
+    ) -> io::Result<()> {
+        with_io_writer(write, |w| {
+            self.format_zoned_date_time_into(w, date_time, offset, zone_name)
+        })
+    }
+
+    pub fn format_zoned_offset_date_time_into_io(
+        &self,
+        write: &mut impl io::Write,
+        date_time: OffsetDateTime,
+        zone_name: &str,
+    ) -> io::Result<()> {
+        with_io_writer(write, |w| {
+            self.format_zoned_offset_date_time_into(w, date_time, zone_name)
+        })
+    }
+}
+
+impl std::str::FromStr for StrftimeFormat<'static> {
+    type Err = FormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::{datetime, offset};
+
+    use super::{super::format_zoned_date_time, FormatError, FormatOptions, StrftimeFormat};
+
+    #[test]
+    fn test_same_as_uncompiled() -> Result<(), FormatError> {
+        let dt = datetime!(2022-03-06 02:04:06.789);
+        for fmt in [
+            "%a %A %b %B %h %c %C %d %D %e %F %g %G %H %I %j %k %l %m %M %n %p %P",
+            "%r %R %s %.3s %12s %S %f %t %T %u %U %V %w %W %x %X %y %Y %z %Z %%",
+            "literal only",
+            "%-d %_3H %^a %#Z %+6Y %Ec %Od trailing %",
+            "%z %:z %::z %:::z",
+            "%3f %-f %N %6N",
+            "",
+        ] {
+            let format = StrftimeFormat::new(fmt)?;
+            assert_eq!(
+                format.format_zoned_date_time(dt, offset!(+9:00), "JST")?,
+                format_zoned_date_time(fmt, dt, offset!(+9:00), "JST")?
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_reusable() -> Result<(), FormatError> {
+        let format: StrftimeFormat = "%F %T %z".parse()?;
+        assert_eq!(
+            format.format_date_time(datetime!(2022-03-06 12:34:56))?,
+            "2022-03-06 12:34:56 "
+        );
+        assert_eq!(
+            format.format_offset_date_time(datetime!(2000-01-02 03:04:05 -1:30))?,
+            "2000-01-02 03:04:05 -0130"
+        );
+        assert_eq!(
+            format.format_zoned_offset_date_time(datetime!(1999-12-31 23:59:59 UTC), "UTC")?,
+            "1999-12-31 23:59:59 +0000"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_into() -> Result<(), Box<dyn std::error::Error>> {
+        let format = StrftimeFormat::new("%F %T|")?;
+        let mut buf = String::new();
+        let mut bytes = Vec::new();
+        for dt in [
+            datetime!(2022-03-06 12:34:56),
+            datetime!(2000-01-02 03:04:05),
+        ] {
+            format.format_date_time_into(&mut buf, dt)?;
+            format.format_date_time_into_io(&mut bytes, dt)?;
+        }
+        assert_eq!(buf, "2022-03-06 12:34:56|2000-01-02 03:04:05|");
+        assert_eq!(bytes, buf.as_bytes());
+        Ok(())
+    }
+
+    #[test]
+    fn test_options() -> Result<(), FormatError> {
+        let dt = datetime!(2022-03-06 12:34:56);
+        let format = StrftimeFormat::new("%s")?;
+        assert_eq!(format.format_date_time(dt)?, "1646570096");
+        let format = format.with_options(FormatOptions::new().require_offset_for_timestamp(true));
+        assert_eq!(format.format_date_time(dt), Err(FormatError::MissingOffset));
+        assert_eq!(
+            format.format_offset_date_time(dt.assume_offset(offset!(+9:00)))?,
+            "1646537696"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_round_subseconds() -> Result<(), FormatError> {
+        let options = FormatOptions::new().round_subseconds(true);
+        let format = StrftimeFormat::new("%F %T.%3f")?;
+        let rounding = format.clone().with_options(options);
+        for (dt, truncated, rounded) in [
+            (
+                datetime!(2022-03-06 12:34:56.1234),
+                "2022-03-06 12:34:56.123",
+                "2022-03-06 12:34:56.123",
+            ),
+            (
+                datetime!(2022-03-06 12:34:56.1235),
+                "2022-03-06 12:34:56.123",
+                "2022-03-06 12:34:56.124",
+            ),
+            (
+                datetime!(2022-12-31 23:59:59.9996),
+                "2022-12-31 23:59:59.999",
+                "2023-01-01 00:00:00.000",
+            ),
+        ] {
+            assert_eq!(format.format_date_time(dt)?, truncated);
+            assert_eq!(rounding.format_date_time(dt)?, rounded);
+        }
+        let rounding = StrftimeFormat::new("%T.%-2f %6f")?.with_options(options);
+        assert_eq!(
+            rounding.format_date_time(datetime!(2022-03-06 12:34:56.9999996))?,
+            "12:34:57.0 000000"
+        );
+        assert_eq!(
+            rounding.format_date_time(datetime!(2022-03-06 12:34:56.9999994))?,
+            "12:34:56.99 999999"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown_specifier() {
+        assert_eq!(
+            StrftimeFormat::new("%Y %q").unwrap_err(),
+            FormatError::UnknownSpecifier('q')
+        );
+        assert_eq!(
+            StrftimeFormat::new("%.12s").unwrap_err(),
+            FormatError::UnsupportedPrecision('s', 12)
+        );
+        assert_eq!(
+            StrftimeFormat::new("%2000H").unwrap_err(),
+            FormatError::UnsupportedWidth('H', 2000)
+        );
+    }
+}
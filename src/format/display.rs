@@ -0,0 +1,222 @@
+use std::fmt::{self, Display, Write};
+
+use time::{Date, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset};
+
+use crate::locale::{Expanding, Locale, PosixLocale};
+
+use super::{
+    spec_parser::{self, Spec},
+    FormatCollector, FormatError, FormatOptions,
+};
+
+/// Formats a date time with a `strftime`-like conversion specification lazily, when it's
+/// displayed.
+///
+/// Errors like an unknown specifier surface as `fmt::Error` while being displayed. Use
+/// [`Strftime::validated`] to catch them up front.
+///
+/// ```
+/// use time::macros::datetime;
+/// use time_fmt::format::Strftime;
+///
+/// let dt = datetime!(2022-03-06 12:34:56);
+/// assert_eq!(
+///     format!("[{}] hello", Strftime::date_time("%F %T", dt)),
+///     "[2022-03-06 12:34:56] hello"
+/// );
+/// assert!(Strftime::date_time("%q", dt).validated().is_err());
+/// ```
+#[derive(Clone, Copy)]
+pub struct Strftime<'a> {
+    fmt: &'a str,
+    date: Date,
+    time: Time,
+    offset: Option<UtcOffset>,
+    zone_name: Option<&'a str>,
+    options: FormatOptions,
+    locale: &'a dyn Locale,
+}
+
+impl fmt::Debug for Strftime<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Strftime")
+            .field("fmt", &self.fmt)
+            .field("date", &self.date)
+            .field("time", &self.time)
+            .field("offset", &self.offset)
+            .field("zone_name", &self.zone_name)
+            .field("options", &self.options)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Discards everything, to see if formatting succeeds.
+struct Discard;
+
+impl Write for Discard {
+    #[inline]
+    fn write_str(&mut self, _s: &str) -> fmt::Result {
+        Ok(())
+    }
+}
+
+impl<'a> Strftime<'a> {
+    pub fn date_time(fmt: &'a str, date_time: PrimitiveDateTime) -> Self {
+        Self {
+            fmt,
+            date: date_time.date(),
+            time: date_time.time(),
+            offset: None,
+            zone_name: None,
+            options: FormatOptions::default(),
+            locale: &PosixLocale,
+        }
+    }
+
+    pub fn offset_date_time(fmt: &'a str, date_time: OffsetDateTime) -> Self {
+        Self {
+            fmt,
+            date: date_time.date(),
+            time: date_time.time(),
+            offset: Some(date_time.offset()),
+            zone_name: None,
+            options: FormatOptions::default(),
+            locale: &PosixLocale,
+        }
+    }
+
+    pub fn zoned_date_time(
+        fmt: &'a str,
+        date_time: PrimitiveDateTime,
+        offset: UtcOffset,
+        zone_name: &'a str,
+    ) -> Self {
+        Self {
+            fmt,
+            date: date_time.date(),
+            time: date_time.time(),
+            offset: Some(offset),
+            zone_name: Some(zone_name),
+            options: FormatOptions::default(),
+            locale: &PosixLocale,
+        }
+    }
+
+    pub fn zoned_offset_date_time(
+        fmt: &'a str,
+        date_time: OffsetDateTime,
+        zone_name: &'a str,
+    ) -> Self {
+        Self {
+            fmt,
+            date: date_time.date(),
+            time: date_time.time(),
+            offset: Some(date_time.offset()),
+            zone_name: Some(zone_name),
+            options: FormatOptions::default(),
+            locale: &PosixLocale,
+        }
+    }
+
+    /// Replaces the options used to format the date time.
+    pub fn with_options(self, options: FormatOptions) -> Self {
+        Self { options, ..self }
+    }
+
+    /// Replaces the locale, the C/POSIX one by default, used to format the date time.
+    pub fn with_locale(self, locale: &'a dyn Locale) -> Self {
+        Self { locale, ..self }
+    }
+
+    /// Returns `self` back if displaying it succeeds, or the error it would have run into.
+    pub fn validated(self) -> Result<Self, FormatError> {
+        self.write_to(&mut Discard)?;
+        Ok(self)
+    }
+
+    fn write_to<W: Write>(&self, write: &mut W) -> Result<(), FormatError> {
+        let collector = FormatCollector {
+            date: self.date,
+            time: self.time,
+            offset: self.offset,
+            zone_name: self.zone_name,
+            options: self.options,
+            locale: self.locale,
+            expanding: Expanding::default(),
+            case: Spec::default(),
+            write,
+        }
+        .with_options_for(self.fmt, self.options)?;
+        spec_parser::parse_conversion_specifications(self.fmt, collector)
+    }
+}
+
+impl<'a> Display for Strftime<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_to(f).map_err(|_| fmt::Error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::{datetime, offset};
+
+    use super::{super::format_zoned_date_time, FormatError, FormatOptions, Strftime};
+
+    #[test]
+    fn test_same_as_eager() -> Result<(), FormatError> {
+        let dt = datetime!(2022-03-06 02:04:06.789);
+        for fmt in ["%c %z %Z", "%-d %_3H %^a %#Z %+6Y", "literal only", ""] {
+            assert_eq!(
+                Strftime::zoned_date_time(fmt, dt, offset!(+9:00), "JST").to_string(),
+                format_zoned_date_time(fmt, dt, offset!(+9:00), "JST")?
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_constructors() -> Result<(), FormatError> {
+        let dt = datetime!(2022-03-06 12:34:56);
+        let odt = dt.assume_offset(offset!(-1:30));
+        assert_eq!(
+            format!("{}", Strftime::date_time("%T%z", dt).validated()?),
+            "12:34:56"
+        );
+        assert_eq!(
+            format!("{}", Strftime::offset_date_time("%T%z", odt)),
+            "12:34:56-0130"
+        );
+        assert_eq!(
+            format!("{}", Strftime::zoned_offset_date_time("%T %Z", odt, "XST")),
+            "12:34:56 XST"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_options() {
+        let dt = datetime!(2022-12-31 23:59:59.9996);
+        let strftime = Strftime::date_time("%F %T.%3f", dt);
+        assert_eq!(strftime.to_string(), "2022-12-31 23:59:59.999");
+        let options = FormatOptions::new().round_subseconds(true);
+        assert_eq!(
+            strftime.with_options(options).to_string(),
+            "2023-01-01 00:00:00.000"
+        );
+    }
+
+    #[test]
+    fn test_error() {
+        use std::fmt::Write;
+
+        let dt = datetime!(2022-03-06 12:34:56);
+        let strftime = Strftime::date_time("%F %q", dt);
+        assert_eq!(
+            strftime.validated().unwrap_err(),
+            FormatError::UnknownSpecifier('q')
+        );
+        let mut buf = String::new();
+        assert!(write!(buf, "{}", strftime).is_err());
+    }
+}
@@ -1,106 +1,169 @@
 use std::slice::SliceIndex;
 
-/// E and O are not implemented.
+use crate::util::Modifier;
+
+/// The widest minimum field width accepted, so that a format like `%99999999999d` fails instead of
+/// writing that many padding characters.
+pub(crate) const MAX_WIDTH: usize = 1024;
+
+/// Padding requested by a flag.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Padding {
+    /// `-`. Don't pad at all.
+    None,
+    /// `_`. Pad with spaces.
+    Space,
+    /// `0`. Pad with zeros.
+    Zero,
+    /// `+`. Pad with zeros, and put `+` before years that have more digits than usual.
+    ZeroWithSign,
+}
+
+/// Flags and the minimum field width that comes between `%` and the conversion specifier, e.g.
+/// `_3` of `%_3d`. The default value means "as POSIX says".
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Spec {
+    /// `-`, `_`, `0` or `+`. The last one wins.
+    pub(crate) padding: Option<Padding>,
+    /// `^`. Convert alphabetic characters to upper case.
+    pub(crate) upper: bool,
+    /// `#`. Swap the case; upper for names, lower for `%p` and `%Z`.
+    pub(crate) swap_case: bool,
+    /// Minimum field width.
+    pub(crate) width: Option<usize>,
+    /// `.N` after the width, only allowed before `s`.
+    pub(crate) precision: Option<usize>,
+    /// The number of `:`s, only allowed up to 3 before `z`.
+    pub(crate) colons: u8,
+    /// `E` or `O`.
+    pub(crate) modifier: Option<Modifier>,
+}
+
+impl Spec {
+    /// What to pass to the components of a composite specifier like `%c`.
+    /// Only case conversions are passed down, padding, width, precision, colons and the modifier
+    /// are ignored.
+    #[inline]
+    pub(crate) fn inherited(self) -> Self {
+        Self {
+            upper: self.upper,
+            swap_case: self.swap_case,
+            ..Self::default()
+        }
+    }
+}
+
+/// E and O modifiers are passed as `spec.modifier` to the same method as the unmodified one; it's
+/// fine to ignore them as that's what POSIX locale does.
 /// Those require `nl-langinfo` lookup is default-implemented as if it were a POSIX locale.
 /// If you'd want to implement it properly, it's your responsibility to recursively parse
-/// the format you get from `nl-langinfo`, and prevent infinite recursion.
+/// the format you get from `nl-langinfo` with `collect`, and prevent infinite recursion.
 pub(crate) trait Collector {
     type Output;
     type Error;
     /// `%a`. `nl_langinfo`-dependent.
-    fn day_of_week_name_short(&mut self) -> Result<(), Self::Error>;
+    fn day_of_week_name_short(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%A`. `nl_langinfo`-dependent.
-    fn day_of_week_name_long(&mut self) -> Result<(), Self::Error>;
+    fn day_of_week_name_long(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%b` and `%h`. `nl_langinfo`-dependent.
-    fn month_name_short(&mut self) -> Result<(), Self::Error>;
+    fn month_name_short(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%B`. `nl_langinfo`-dependent.
-    fn month_name_long(&mut self) -> Result<(), Self::Error>;
+    fn month_name_long(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%c`. Same as `%a %b %e %T %Y` in POSIX locale. `nl_langinfo`-dependent.
     #[inline]
-    fn preferred_date_time(&mut self) -> Result<(), Self::Error> {
-        self.day_of_week_name_short()?;
+    fn preferred_date_time(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let spec = spec.inherited();
+        self.day_of_week_name_short(spec)?;
         self.static_str(" ")?;
-        self.month_name_short()?;
+        self.month_name_short(spec)?;
         self.static_str(" ")?;
-        self.day_of_month_blank()?;
+        self.day_of_month_blank(spec)?;
         self.static_str(" ")?;
-        self.time_of_day()?;
+        self.time_of_day(spec)?;
         self.static_str(" ")?;
-        self.year()
+        self.year(spec)
     }
     /// `%C`. `00` to unbounded number.
-    fn year_prefix(&mut self) -> Result<(), Self::Error>;
+    fn year_prefix(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%d`. `01` to `31`.
-    fn day_of_month(&mut self) -> Result<(), Self::Error>;
+    fn day_of_month(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%D`. `%m/%d/%y` (American......).
     #[inline]
-    fn date_mmddyy_slash(&mut self) -> Result<(), Self::Error> {
-        self.month_of_year()?;
+    fn date_mmddyy_slash(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let spec = spec.inherited();
+        self.month_of_year(spec)?;
         self.static_str("/")?;
-        self.day_of_month()?;
+        self.day_of_month(spec)?;
         self.static_str("/")?;
-        self.year_suffix()
+        self.year_suffix(spec)
     }
     /// `%e`. ` 1` to `31`.
-    fn day_of_month_blank(&mut self) -> Result<(), Self::Error>;
+    fn day_of_month_blank(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%F`. `%Y-%m-%d`.
     #[inline]
-    fn date_yyyymmdd_hyphen(&mut self) -> Result<(), Self::Error> {
-        self.year()?;
+    fn date_yyyymmdd_hyphen(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let spec = spec.inherited();
+        self.year(spec)?;
         self.static_str("-")?;
-        self.month_of_year()?;
+        self.month_of_year(spec)?;
         self.static_str("-")?;
-        self.day_of_month()
+        self.day_of_month(spec)
     }
     /// `%g`. ISO 8601 week-based year modulo 100.
-    fn iso8601_week_based_year_suffix(&mut self) -> Result<(), Self::Error>;
+    fn iso8601_week_based_year_suffix(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%G`. ISO 8601 week-based year.
-    fn iso8601_week_based_year(&mut self) -> Result<(), Self::Error>;
+    fn iso8601_week_based_year(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%H`. `00` to `23`.
-    fn hour_of_day(&mut self) -> Result<(), Self::Error>;
+    fn hour_of_day(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%I`. `01` to `12`.
-    fn hour_of_day_12(&mut self) -> Result<(), Self::Error>;
+    fn hour_of_day_12(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%j`. `001` to `336`.
-    fn day_of_year(&mut self) -> Result<(), Self::Error>;
+    fn day_of_year(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%k`. ` 0` to `23`.
-    fn hour_of_day_blank(&mut self) -> Result<(), Self::Error>;
+    fn hour_of_day_blank(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%l`. ` 1` to `12`.
-    fn hour_of_day_12_blank(&mut self) -> Result<(), Self::Error>;
+    fn hour_of_day_12_blank(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%m`. `01` to `12`.
-    fn month_of_year(&mut self) -> Result<(), Self::Error>;
+    fn month_of_year(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%M`. `00` to `59`.
-    fn minute_of_hour(&mut self) -> Result<(), Self::Error>;
+    fn minute_of_hour(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%n`.
     #[inline]
     fn new_line(&mut self) -> Result<(), Self::Error> {
         self.static_str("\n")
     }
     /// `%p`. `AM` or `PM`. `nl_langinfo`-dependent.
-    fn ampm(&mut self) -> Result<(), Self::Error>;
+    fn ampm(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%P`. `am` or `pm`. `nl_langinfo`-dependent.
-    fn ampm_lower(&mut self) -> Result<(), Self::Error>;
+    fn ampm_lower(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%r`. Same as `%I:%M:%S %p` in POSIX locale. `nl_langinfo`-dependent.
     #[inline]
-    fn time_ampm(&mut self) -> Result<(), Self::Error> {
-        self.hour_of_day_12()?;
+    fn time_ampm(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let spec = spec.inherited();
+        self.hour_of_day_12(spec)?;
         self.static_str(":")?;
-        self.minute_of_hour()?;
+        self.minute_of_hour(spec)?;
         self.static_str(":")?;
-        self.second_of_minute()?;
+        self.second_of_minute(spec)?;
         self.static_str(" ")?;
-        self.ampm()
+        self.ampm(spec)
     }
     /// `%R`. Same as `%H:%M`.
     #[inline]
-    fn hour_minute_of_day(&mut self) -> Result<(), Self::Error> {
-        self.hour_of_day()?;
+    fn hour_minute_of_day(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let spec = spec.inherited();
+        self.hour_of_day(spec)?;
         self.static_str(":")?;
-        self.minute_of_hour()
+        self.minute_of_hour(spec)
     }
+    /// `%s`. Seconds since the Unix epoch. With a precision `.N` (up to 9), in units of `10^-N`
+    /// seconds instead, e.g. `%.3s` for milliseconds.
+    fn seconds_since_epoch(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%S`. `00` to `60`.
-    fn second_of_minute(&mut self) -> Result<(), Self::Error>;
-    /// `%f`. `000000000` to `999999999`.
-    fn nanosecond_of_second(&mut self) -> Result<(), Self::Error>;
+    fn second_of_minute(&mut self, spec: Spec) -> Result<(), Self::Error>;
+    /// `%f` and `%N`. `000000000` to `999999999`. With a minimum field width `N` (up to 9), `N`
+    /// digits instead, e.g. `%3f` for milliseconds. `-` flag trims trailing zeros.
+    fn nanosecond_of_second(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%t`.
     #[inline]
     fn tab(&mut self) -> Result<(), Self::Error> {
@@ -108,49 +171,55 @@ pub(crate) trait Collector {
     }
     /// `%T`. Same as `%H:%M:%S`.
     #[inline]
-    fn time_of_day(&mut self) -> Result<(), Self::Error> {
-        self.hour_of_day()?;
+    fn time_of_day(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let spec = spec.inherited();
+        self.hour_of_day(spec)?;
         self.static_str(":")?;
-        self.minute_of_hour()?;
+        self.minute_of_hour(spec)?;
         self.static_str(":")?;
-        self.second_of_minute()
+        self.second_of_minute(spec)
     }
     /// `%u`. `1` to `7`
-    fn day_of_week_from_monday_as_1(&mut self) -> Result<(), Self::Error>;
+    fn day_of_week_from_monday_as_1(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%U`. `00` to `53`.
-    fn week_number_of_current_year_start_sunday(&mut self) -> Result<(), Self::Error>;
+    fn week_number_of_current_year_start_sunday(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%V`. `01` to `53`.
-    fn iso8601_week_number(&mut self) -> Result<(), Self::Error>;
+    fn iso8601_week_number(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%w`.
-    fn day_of_week_from_sunday_as_0(&mut self) -> Result<(), Self::Error>;
+    fn day_of_week_from_sunday_as_0(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%W`. `00` to `53`.
-    fn week_number_of_current_year_start_monday(&mut self) -> Result<(), Self::Error>;
+    fn week_number_of_current_year_start_monday(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%x`. `%m/%d/%y` in POSIX locale. `nl_langinfo`-dependent.
     #[inline]
-    fn preferred_date(&mut self) -> Result<(), Self::Error> {
-        self.month_of_year()?;
+    fn preferred_date(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let spec = spec.inherited();
+        self.month_of_year(spec)?;
         self.static_str("/")?;
-        self.day_of_month()?;
+        self.day_of_month(spec)?;
         self.static_str("/")?;
-        self.year_suffix()
+        self.year_suffix(spec)
     }
     /// `%X`. `%H:%M:%S` in POSIX locale. `nl_langinfo`-dependent.
     #[inline]
-    fn preferred_time_of_day(&mut self) -> Result<(), Self::Error> {
-        self.hour_of_day()?;
+    fn preferred_time_of_day(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let spec = spec.inherited();
+        self.hour_of_day(spec)?;
         self.static_str(":")?;
-        self.minute_of_hour()?;
+        self.minute_of_hour(spec)?;
         self.static_str(":")?;
-        self.second_of_minute()
+        self.second_of_minute(spec)
     }
     /// `%y`. `00` to `99`.
-    fn year_suffix(&mut self) -> Result<(), Self::Error>;
+    fn year_suffix(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%Y`.
-    fn year(&mut self) -> Result<(), Self::Error>;
-    /// `%z`. `+hhmm` or `-hhmm`.
-    fn timezone(&mut self) -> Result<(), Self::Error>;
+    fn year(&mut self, spec: Spec) -> Result<(), Self::Error>;
+    /// `%z`. `+hhmm` or `-hhmm`, `+hhmmss` if the offset has seconds.
+    /// `%:z`. `+hh:mm`, `+hh:mm:ss` if the offset has seconds.
+    /// `%::z`. `+hh:mm:ss`.
+    /// `%:::z`. `+hh`, `+hh:mm` or `+hh:mm:ss`, whichever is the shortest.
+    fn timezone(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%Z`. Timezone name or abbreviation.
-    fn timezone_name(&mut self) -> Result<(), Self::Error>;
+    fn timezone_name(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%%`.
     #[inline]
     fn percent(&mut self) -> Result<(), Self::Error> {
@@ -170,15 +239,24 @@ pub(crate) trait Collector {
     ) -> Result<(), Self::Error>;
     /// `%(something else)`.
     fn unknown(&mut self, specifier: char) -> Result<(), Self::Error>;
+    /// A conversion specifier with a minimum field width wider than [`MAX_WIDTH`].
+    fn too_wide(&mut self, specifier: char, width: usize) -> Result<(), Self::Error>;
 
     /// Construct the final result from what you've collected.
     fn output(self) -> Result<Self::Output, Self::Error>;
 }
 
 pub(crate) fn parse_conversion_specifications<C: Collector>(
-    mut format: &str,
+    format: &str,
     mut collector: C,
 ) -> Result<C::Output, C::Error> {
+    collect(format, &mut collector)?;
+    collector.output()
+}
+
+/// Feeds `format` to `collector` without finishing it, so that a collector can expand a format in
+/// the middle of another one.
+pub(crate) fn collect<C: Collector>(mut format: &str, collector: &mut C) -> Result<(), C::Error> {
     let original_len = format.len();
     while !format.is_empty() {
         let i = format
@@ -196,47 +274,64 @@ pub(crate) fn parse_conversion_specifications<C: Collector>(
         }
         assert_eq!(format.as_bytes()[0], b'%');
         format = &format[1..];
-        if let Some(b) = format.bytes().next() {
+        let (spec, rest) = parse_spec(format);
+        if let Some(b) = rest.bytes().next() {
+            format = rest;
+            if let Some(width) = spec.width.filter(|&width| width > MAX_WIDTH) {
+                let c = format.chars().next().unwrap();
+                collector.too_wide(c, width)?;
+                format = &format[c.len_utf8()..];
+                continue;
+            }
+            if spec.colons > 0 && (b != b'z' || spec.colons > 3) {
+                collector.unknown(':')?;
+                continue;
+            }
+            if spec.precision.is_some() && b != b's' {
+                collector.unknown('.')?;
+                continue;
+            }
             match b {
-                b'a' => collector.day_of_week_name_short()?,
-                b'A' => collector.day_of_week_name_long()?,
-                b'b' | b'h' => collector.month_name_short()?,
-                b'B' => collector.month_name_long()?,
-                b'c' => collector.preferred_date_time()?,
-                b'C' => collector.year_prefix()?,
-                b'd' => collector.day_of_month()?,
-                b'D' => collector.date_mmddyy_slash()?,
-                b'e' => collector.day_of_month_blank()?,
-                b'F' => collector.date_yyyymmdd_hyphen()?,
-                b'g' => collector.iso8601_week_based_year_suffix()?,
-                b'G' => collector.iso8601_week_based_year()?,
-                b'H' => collector.hour_of_day()?,
-                b'I' => collector.hour_of_day_12()?,
-                b'j' => collector.day_of_year()?,
-                b'k' => collector.hour_of_day_blank()?,
-                b'l' => collector.hour_of_day_12_blank()?,
-                b'm' => collector.month_of_year()?,
-                b'M' => collector.minute_of_hour()?,
+                b'a' => collector.day_of_week_name_short(spec)?,
+                b'A' => collector.day_of_week_name_long(spec)?,
+                b'b' | b'h' => collector.month_name_short(spec)?,
+                b'B' => collector.month_name_long(spec)?,
+                b'c' => collector.preferred_date_time(spec)?,
+                b'C' => collector.year_prefix(spec)?,
+                b'd' => collector.day_of_month(spec)?,
+                b'D' => collector.date_mmddyy_slash(spec)?,
+                b'e' => collector.day_of_month_blank(spec)?,
+                b'F' => collector.date_yyyymmdd_hyphen(spec)?,
+                b'g' => collector.iso8601_week_based_year_suffix(spec)?,
+                b'G' => collector.iso8601_week_based_year(spec)?,
+                b'H' => collector.hour_of_day(spec)?,
+                b'I' => collector.hour_of_day_12(spec)?,
+                b'j' => collector.day_of_year(spec)?,
+                b'k' => collector.hour_of_day_blank(spec)?,
+                b'l' => collector.hour_of_day_12_blank(spec)?,
+                b'm' => collector.month_of_year(spec)?,
+                b'M' => collector.minute_of_hour(spec)?,
                 b'n' => collector.new_line()?,
-                b'p' => collector.ampm()?,
-                b'P' => collector.ampm_lower()?,
-                b'r' => collector.time_ampm()?,
-                b'R' => collector.hour_minute_of_day()?,
-                b'S' => collector.second_of_minute()?,
-                b'f' => collector.nanosecond_of_second()?,
+                b'p' => collector.ampm(spec)?,
+                b'P' => collector.ampm_lower(spec)?,
+                b'r' => collector.time_ampm(spec)?,
+                b'R' => collector.hour_minute_of_day(spec)?,
+                b's' => collector.seconds_since_epoch(spec)?,
+                b'S' => collector.second_of_minute(spec)?,
+                b'f' | b'N' => collector.nanosecond_of_second(spec)?,
                 b't' => collector.tab()?,
-                b'T' => collector.time_of_day()?,
-                b'u' => collector.day_of_week_from_monday_as_1()?,
-                b'U' => collector.week_number_of_current_year_start_sunday()?,
-                b'V' => collector.iso8601_week_number()?,
-                b'w' => collector.day_of_week_from_sunday_as_0()?,
-                b'W' => collector.week_number_of_current_year_start_monday()?,
-                b'x' => collector.preferred_date()?,
-                b'X' => collector.preferred_time_of_day()?,
-                b'y' => collector.year_suffix()?,
-                b'Y' => collector.year()?,
-                b'z' => collector.timezone()?,
-                b'Z' => collector.timezone_name()?,
+                b'T' => collector.time_of_day(spec)?,
+                b'u' => collector.day_of_week_from_monday_as_1(spec)?,
+                b'U' => collector.week_number_of_current_year_start_sunday(spec)?,
+                b'V' => collector.iso8601_week_number(spec)?,
+                b'w' => collector.day_of_week_from_sunday_as_0(spec)?,
+                b'W' => collector.week_number_of_current_year_start_monday(spec)?,
+                b'x' => collector.preferred_date(spec)?,
+                b'X' => collector.preferred_time_of_day(spec)?,
+                b'y' => collector.year_suffix(spec)?,
+                b'Y' => collector.year(spec)?,
+                b'z' => collector.timezone(spec)?,
+                b'Z' => collector.timezone_name(spec)?,
                 b'%' => collector.percent()?,
                 _ => {
                     let c = format.chars().next().unwrap();
@@ -250,5 +345,54 @@ pub(crate) fn parse_conversion_specifications<C: Collector>(
             collector.percent()?;
         }
     }
-    collector.output()
+    Ok(())
+}
+
+/// Parses flags, the minimum field width, the precision, colons and the modifier, and returns them
+/// with the rest of the format.
+fn parse_spec(format: &str) -> (Spec, &str) {
+    let mut spec = Spec::default();
+    let bytes = format.as_bytes();
+    let mut i = 0;
+    while let Some(&b) = bytes.get(i) {
+        match b {
+            b'-' => spec.padding = Some(Padding::None),
+            b'_' => spec.padding = Some(Padding::Space),
+            b'0' => spec.padding = Some(Padding::Zero),
+            b'+' => spec.padding = Some(Padding::ZeroWithSign),
+            b'^' => spec.upper = true,
+            b'#' => spec.swap_case = true,
+            _ => break,
+        }
+        i += 1;
+    }
+    while let Some(&b) = bytes.get(i) {
+        if !b.is_ascii_digit() {
+            break;
+        }
+        let width = spec.width.unwrap_or(0);
+        spec.width = Some(width.saturating_mul(10).saturating_add((b - b'0') as usize));
+        i += 1;
+    }
+    if bytes.get(i) == Some(&b'.') {
+        i += 1;
+        let mut precision = 0usize;
+        while let Some(&b) = bytes.get(i).filter(|b| b.is_ascii_digit()) {
+            precision = precision
+                .saturating_mul(10)
+                .saturating_add((b - b'0') as usize);
+            i += 1;
+        }
+        spec.precision = Some(precision);
+    }
+    while bytes.get(i) == Some(&b':') {
+        spec.colons = spec.colons.saturating_add(1);
+        i += 1;
+    }
+    match bytes.get(i) {
+        Some(b'E') => spec.modifier = Some(Modifier::Era),
+        Some(b'O') => spec.modifier = Some(Modifier::AlternativeDigits),
+        _ => return (spec, &format[i..]),
+    }
+    (spec, &format[(i + 1)..])
 }
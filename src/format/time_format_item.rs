@@ -3,7 +3,7 @@ use std::slice::SliceIndex;
 use thiserror::Error;
 use time::format_description::{modifier, Component, FormatItem};
 
-use super::spec_parser::Collector;
+use super::spec_parser::{Collector, Padding, Spec};
 
 #[derive(Error, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 #[non_exhaustive]
@@ -28,12 +28,70 @@ impl<'a> ToFormatItemCollector<'a> {
     }
 }
 
+/// Rejects flags and width that `FormatItem` can't express on a name.
+#[inline]
+fn no_flags(spec: Spec) -> Result<(), Error> {
+    if spec.width.is_some() {
+        Err(Error::NoCorrespondingFormatItem("minimum field width"))
+    } else if spec.upper || spec.swap_case {
+        Err(Error::NoCorrespondingFormatItem("case conversion"))
+    } else {
+        Ok(())
+    }
+}
+
+/// Translates the padding flags to that of `FormatItem`, where `+` is treated as `0`.
+#[inline]
+fn padding(spec: Spec, default: modifier::Padding) -> Result<modifier::Padding, Error> {
+    no_flags(spec)?;
+    Ok(match spec.padding {
+        None => default,
+        Some(Padding::None) => modifier::Padding::None,
+        Some(Padding::Space) => modifier::Padding::Space,
+        Some(Padding::Zero | Padding::ZeroWithSign) => modifier::Padding::Zero,
+    })
+}
+
+/// `SubsecondDigits` of `%f`; the exact number of digits, or as many as needed with `-` flag.
+fn subsecond_digits(spec: Spec) -> Result<modifier::SubsecondDigits, Error> {
+    use modifier::SubsecondDigits::*;
+
+    if spec.upper || spec.swap_case {
+        return Err(Error::NoCorrespondingFormatItem("case conversion"));
+    }
+    Ok(match (spec.padding, spec.width) {
+        (Some(Padding::None), None) => OneOrMore,
+        (Some(Padding::None), Some(_)) => {
+            return Err(Error::NoCorrespondingFormatItem(
+                "`-` flag with a field width",
+            ))
+        }
+        (_, None) => modifier::Subsecond::default().digits,
+        (_, Some(width)) => [One, Two, Three, Four, Five, Six, Seven, Eight, Nine]
+            .get(width.wrapping_sub(1))
+            .copied()
+            .ok_or(Error::NoCorrespondingFormatItem(
+                "fractional digits other than 1 to 9",
+            ))?,
+    })
+}
+
+/// Same as `padding` but for years, that rejects `+`.
+#[inline]
+fn year_padding(spec: Spec) -> Result<modifier::Padding, Error> {
+    if spec.padding == Some(Padding::ZeroWithSign) {
+        return Err(Error::NoCorrespondingFormatItem("`+` flag"));
+    }
+    padding(spec, modifier::Padding::Zero)
+}
+
 impl<'a> Collector for ToFormatItemCollector<'a> {
     type Output = Vec<FormatItem<'a>>;
     type Error = Error;
 
     #[inline]
-    fn day_of_week_name_short(&mut self) -> Result<(), Self::Error> {
+    fn day_of_week_name_short(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        no_flags(spec)?;
         let mut modifier = modifier::Weekday::default();
         modifier.repr = modifier::WeekdayRepr::Short;
         self.items
@@ -42,7 +100,8 @@ impl<'a> Collector for ToFormatItemCollector<'a> {
     }
 
     #[inline]
-    fn day_of_week_name_long(&mut self) -> Result<(), Self::Error> {
+    fn day_of_week_name_long(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        no_flags(spec)?;
         let mut modifier = modifier::Weekday::default();
         modifier.repr = modifier::WeekdayRepr::Long;
         self.items
@@ -51,7 +110,8 @@ impl<'a> Collector for ToFormatItemCollector<'a> {
     }
 
     #[inline]
-    fn month_name_short(&mut self) -> Result<(), Self::Error> {
+    fn month_name_short(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        no_flags(spec)?;
         let mut modifier = modifier::Month::default();
         modifier.repr = modifier::MonthRepr::Short;
         self.items
@@ -60,7 +120,8 @@ impl<'a> Collector for ToFormatItemCollector<'a> {
     }
 
     #[inline]
-    fn month_name_long(&mut self) -> Result<(), Self::Error> {
+    fn month_name_long(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        no_flags(spec)?;
         let mut modifier = modifier::Month::default();
         modifier.repr = modifier::MonthRepr::Long;
         self.items
@@ -69,110 +130,118 @@ impl<'a> Collector for ToFormatItemCollector<'a> {
     }
 
     #[inline]
-    fn year_prefix(&mut self) -> Result<(), Self::Error> {
+    fn year_prefix(&mut self, _spec: Spec) -> Result<(), Self::Error> {
         Err(Self::Error::NoCorrespondingFormatItem("%C"))
     }
 
     #[inline]
-    fn day_of_month(&mut self) -> Result<(), Self::Error> {
+    fn day_of_month(&mut self, spec: Spec) -> Result<(), Self::Error> {
         let mut modifier = modifier::Day::default();
-        modifier.padding = modifier::Padding::Zero;
+        modifier.padding = padding(spec, modifier::Padding::Zero)?;
         self.items
             .push(FormatItem::Component(Component::Day(modifier)));
         Ok(())
     }
 
     #[inline]
-    fn day_of_month_blank(&mut self) -> Result<(), Self::Error> {
+    fn day_of_month_blank(&mut self, spec: Spec) -> Result<(), Self::Error> {
         let mut modifier = modifier::Day::default();
-        modifier.padding = modifier::Padding::Space;
+        modifier.padding = padding(spec, modifier::Padding::Space)?;
         self.items
             .push(FormatItem::Component(Component::Day(modifier)));
         Ok(())
     }
 
     #[inline]
-    fn iso8601_week_based_year_suffix(&mut self) -> Result<(), Self::Error> {
+    fn iso8601_week_based_year_suffix(&mut self, spec: Spec) -> Result<(), Self::Error> {
         let mut modifier = modifier::Year::default();
         modifier.iso_week_based = true;
         modifier.repr = modifier::YearRepr::LastTwo;
+        modifier.padding = padding(spec, modifier::Padding::Zero)?;
         self.items
             .push(FormatItem::Component(Component::Year(modifier)));
         Ok(())
     }
 
     #[inline]
-    fn iso8601_week_based_year(&mut self) -> Result<(), Self::Error> {
+    fn iso8601_week_based_year(&mut self, spec: Spec) -> Result<(), Self::Error> {
         let mut modifier = modifier::Year::default();
         modifier.iso_week_based = true;
+        modifier.padding = year_padding(spec)?;
         self.items
             .push(FormatItem::Component(Component::Year(modifier)));
         Ok(())
     }
 
     #[inline]
-    fn hour_of_day(&mut self) -> Result<(), Self::Error> {
-        let modifier = modifier::Hour::default();
+    fn hour_of_day(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let mut modifier = modifier::Hour::default();
+        modifier.padding = padding(spec, modifier::Padding::Zero)?;
         self.items
             .push(FormatItem::Component(Component::Hour(modifier)));
         Ok(())
     }
 
     #[inline]
-    fn hour_of_day_12(&mut self) -> Result<(), Self::Error> {
+    fn hour_of_day_12(&mut self, spec: Spec) -> Result<(), Self::Error> {
         let mut modifier = modifier::Hour::default();
         modifier.is_12_hour_clock = true;
+        modifier.padding = padding(spec, modifier::Padding::Zero)?;
         self.items
             .push(FormatItem::Component(Component::Hour(modifier)));
         Ok(())
     }
 
     #[inline]
-    fn day_of_year(&mut self) -> Result<(), Self::Error> {
-        let modifier = modifier::Ordinal::default();
+    fn day_of_year(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let mut modifier = modifier::Ordinal::default();
+        modifier.padding = padding(spec, modifier::Padding::Zero)?;
         self.items
             .push(FormatItem::Component(Component::Ordinal(modifier)));
         Ok(())
     }
 
     #[inline]
-    fn hour_of_day_blank(&mut self) -> Result<(), Self::Error> {
+    fn hour_of_day_blank(&mut self, spec: Spec) -> Result<(), Self::Error> {
         let mut modifier = modifier::Hour::default();
-        modifier.padding = modifier::Padding::Space;
+        modifier.padding = padding(spec, modifier::Padding::Space)?;
         self.items
             .push(FormatItem::Component(Component::Hour(modifier)));
         Ok(())
     }
 
     #[inline]
-    fn hour_of_day_12_blank(&mut self) -> Result<(), Self::Error> {
+    fn hour_of_day_12_blank(&mut self, spec: Spec) -> Result<(), Self::Error> {
         let mut modifier = modifier::Hour::default();
-        modifier.padding = modifier::Padding::Space;
         modifier.is_12_hour_clock = true;
+        modifier.padding = padding(spec, modifier::Padding::Space)?;
         self.items
             .push(FormatItem::Component(Component::Hour(modifier)));
         Ok(())
     }
 
     #[inline]
-    fn month_of_year(&mut self) -> Result<(), Self::Error> {
+    fn month_of_year(&mut self, spec: Spec) -> Result<(), Self::Error> {
         let mut modifier = modifier::Month::default();
         modifier.repr = modifier::MonthRepr::Numerical;
+        modifier.padding = padding(spec, modifier::Padding::Zero)?;
         self.items
             .push(FormatItem::Component(Component::Month(modifier)));
         Ok(())
     }
 
     #[inline]
-    fn minute_of_hour(&mut self) -> Result<(), Self::Error> {
-        let modifier = modifier::Minute::default();
+    fn minute_of_hour(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let mut modifier = modifier::Minute::default();
+        modifier.padding = padding(spec, modifier::Padding::Zero)?;
         self.items
             .push(FormatItem::Component(Component::Minute(modifier)));
         Ok(())
     }
 
     #[inline]
-    fn ampm(&mut self) -> Result<(), Self::Error> {
+    fn ampm(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        no_flags(spec)?;
         let mut modifier = modifier::Period::default();
         modifier.is_uppercase = true;
         self.items
@@ -181,7 +250,8 @@ impl<'a> Collector for ToFormatItemCollector<'a> {
     }
 
     #[inline]
-    fn ampm_lower(&mut self) -> Result<(), Self::Error> {
+    fn ampm_lower(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        no_flags(spec)?;
         let mut modifier = modifier::Period::default();
         modifier.is_uppercase = false;
         self.items
@@ -190,23 +260,34 @@ impl<'a> Collector for ToFormatItemCollector<'a> {
     }
 
     #[inline]
-    fn second_of_minute(&mut self) -> Result<(), Self::Error> {
-        let modifier = modifier::Second::default();
+    fn seconds_since_epoch(&mut self, _spec: Spec) -> Result<(), Self::Error> {
+        // `Component::UnixTimestamp` only exists in newer versions of the time crate.
+        Err(Self::Error::NoCorrespondingFormatItem(
+            "seconds since the epoch",
+        ))
+    }
+
+    #[inline]
+    fn second_of_minute(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let mut modifier = modifier::Second::default();
+        modifier.padding = padding(spec, modifier::Padding::Zero)?;
         self.items
             .push(FormatItem::Component(Component::Second(modifier)));
         Ok(())
     }
 
     #[inline]
-    fn nanosecond_of_second(&mut self) -> Result<(), Self::Error> {
-        let modifier = modifier::Subsecond::default();
+    fn nanosecond_of_second(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let mut modifier = modifier::Subsecond::default();
+        modifier.digits = subsecond_digits(spec)?;
         self.items
             .push(FormatItem::Component(Component::Subsecond(modifier)));
         Ok(())
     }
 
     #[inline]
-    fn day_of_week_from_monday_as_1(&mut self) -> Result<(), Self::Error> {
+    fn day_of_week_from_monday_as_1(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        no_flags(spec)?;
         let mut modifier = modifier::Weekday::default();
         modifier.repr = modifier::WeekdayRepr::Monday;
         modifier.one_indexed = true;
@@ -216,25 +297,28 @@ impl<'a> Collector for ToFormatItemCollector<'a> {
     }
 
     #[inline]
-    fn week_number_of_current_year_start_sunday(&mut self) -> Result<(), Self::Error> {
+    fn week_number_of_current_year_start_sunday(&mut self, spec: Spec) -> Result<(), Self::Error> {
         let mut modifier = modifier::WeekNumber::default();
         modifier.repr = modifier::WeekNumberRepr::Sunday;
+        modifier.padding = padding(spec, modifier::Padding::Zero)?;
         self.items
             .push(FormatItem::Component(Component::WeekNumber(modifier)));
         Ok(())
     }
 
     #[inline]
-    fn iso8601_week_number(&mut self) -> Result<(), Self::Error> {
+    fn iso8601_week_number(&mut self, spec: Spec) -> Result<(), Self::Error> {
         let mut modifier = modifier::WeekNumber::default();
         modifier.repr = modifier::WeekNumberRepr::Iso;
+        modifier.padding = padding(spec, modifier::Padding::Zero)?;
         self.items
             .push(FormatItem::Component(Component::WeekNumber(modifier)));
         Ok(())
     }
 
     #[inline]
-    fn day_of_week_from_sunday_as_0(&mut self) -> Result<(), Self::Error> {
+    fn day_of_week_from_sunday_as_0(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        no_flags(spec)?;
         let mut modifier = modifier::Weekday::default();
         modifier.repr = modifier::WeekdayRepr::Sunday;
         modifier.one_indexed = false;
@@ -244,45 +328,61 @@ impl<'a> Collector for ToFormatItemCollector<'a> {
     }
 
     #[inline]
-    fn week_number_of_current_year_start_monday(&mut self) -> Result<(), Self::Error> {
+    fn week_number_of_current_year_start_monday(&mut self, spec: Spec) -> Result<(), Self::Error> {
         let mut modifier = modifier::WeekNumber::default();
         modifier.repr = modifier::WeekNumberRepr::Monday;
+        modifier.padding = padding(spec, modifier::Padding::Zero)?;
         self.items
             .push(FormatItem::Component(Component::WeekNumber(modifier)));
         Ok(())
     }
 
     #[inline]
-    fn year_suffix(&mut self) -> Result<(), Self::Error> {
+    fn year_suffix(&mut self, spec: Spec) -> Result<(), Self::Error> {
         let mut modifier = modifier::Year::default();
         modifier.repr = modifier::YearRepr::LastTwo;
+        modifier.padding = padding(spec, modifier::Padding::Zero)?;
         self.items
             .push(FormatItem::Component(Component::Year(modifier)));
         Ok(())
     }
 
     #[inline]
-    fn year(&mut self) -> Result<(), Self::Error> {
-        let modifier = modifier::Year::default();
+    fn year(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let mut modifier = modifier::Year::default();
+        modifier.padding = year_padding(spec)?;
         self.items
             .push(FormatItem::Component(Component::Year(modifier)));
         Ok(())
     }
 
     #[inline]
-    fn timezone(&mut self) -> Result<(), Self::Error> {
+    fn timezone(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        if spec.colons > 2 {
+            return Err(Self::Error::NoCorrespondingFormatItem("minimal offset"));
+        }
         let mut modifier = modifier::OffsetHour::default();
         modifier.sign_is_mandatory = true;
         self.items
             .push(FormatItem::Component(Component::OffsetHour(modifier)));
+        if spec.colons > 0 {
+            self.items.push(FormatItem::Literal(b":"));
+        }
         let modifier = modifier::OffsetMinute::default();
         self.items
             .push(FormatItem::Component(Component::OffsetMinute(modifier)));
+        // Seconds of `%z` and `%:z` can't be omitted only when they're zero, so dropped always.
+        if spec.colons == 2 {
+            self.items.push(FormatItem::Literal(b":"));
+            let modifier = modifier::OffsetSecond::default();
+            self.items
+                .push(FormatItem::Component(Component::OffsetSecond(modifier)));
+        }
         Ok(())
     }
 
     #[inline]
-    fn timezone_name(&mut self) -> Result<(), Self::Error> {
+    fn timezone_name(&mut self, _spec: Spec) -> Result<(), Self::Error> {
         Err(Self::Error::NoCorrespondingFormatItem("timezone name"))
     }
 
@@ -307,13 +407,20 @@ impl<'a> Collector for ToFormatItemCollector<'a> {
         Err(Self::Error::UnknownSpecifier(specifier))
     }
 
+    #[inline]
+    fn too_wide(&mut self, _specifier: char, _width: usize) -> Result<(), Self::Error> {
+        Err(Self::Error::NoCorrespondingFormatItem(
+            "minimum field width",
+        ))
+    }
+
     #[inline]
     fn output(self) -> Result<Self::Output, Self::Error> {
         Ok(self.items)
     }
 }
 
-pub fn parse_to_format_item(fmt: &str) -> Result<Vec<FormatItem>, Error> {
+pub fn parse_to_format_item(fmt: &str) -> Result<Vec<FormatItem<'_>>, Error> {
     let collector = ToFormatItemCollector::new(fmt.as_bytes());
     super::spec_parser::parse_conversion_specifications(fmt, collector)
 }
@@ -333,6 +440,37 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn flags() -> Result<(), Box<dyn std::error::Error>> {
+        let format_items = parse_to_format_item("%-d/%_m/%0e %-H")?;
+        assert_eq!(
+            datetime!(2012-05-01 02:09:14).format(&format_items)?,
+            "1/ 5/01 2"
+        );
+        let format_items = parse_to_format_item("%3f %-f %6f")?;
+        assert_eq!(
+            datetime!(2012-05-01 02:09:14.1205).format(&format_items)?,
+            "120 1205 120500"
+        );
+        assert_eq!(
+            parse_to_format_item("%10f"),
+            Err(super::Error::NoCorrespondingFormatItem(
+                "fractional digits other than 1 to 9"
+            ))
+        );
+        assert_eq!(
+            parse_to_format_item("%5d"),
+            Err(super::Error::NoCorrespondingFormatItem(
+                "minimum field width"
+            ))
+        );
+        assert_eq!(
+            parse_to_format_item("%^a"),
+            Err(super::Error::NoCorrespondingFormatItem("case conversion"))
+        );
+        Ok(())
+    }
+
     #[test]
     fn parse_primitive_datetime() -> Result<(), Box<dyn std::error::Error>> {
         let format_items = parse_to_format_item("%Y-%m-%d %H:%M:%S")?;
@@ -350,6 +488,15 @@ mod tests {
             datetime!(2012-05-21 12:09:14 +9:00).format(&format_items)?,
             "2012-05-21 12:09:14 +0900"
         );
+        let format_items = parse_to_format_item("%:z %::z")?;
+        assert_eq!(
+            datetime!(2012-05-21 12:09:14 -0:30:15).format(&format_items)?,
+            "-00:30 -00:30:15"
+        );
+        assert_eq!(
+            parse_to_format_item("%:::z"),
+            Err(super::Error::NoCorrespondingFormatItem("minimal offset"))
+        );
         Ok(())
     }
 }
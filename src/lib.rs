@@ -1,3 +1,5 @@
+pub mod calendar;
 pub mod format;
+pub mod locale;
 pub mod parse;
 mod util;
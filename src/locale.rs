@@ -0,0 +1,1103 @@
+use time::{Date, Month, Weekday};
+
+use crate::{
+    calendar::{Calendar, Gregorian},
+    util::{self, date},
+};
+
+#[cfg(feature = "locales")]
+mod data;
+mod digits;
+
+pub use self::digits::AltDigits;
+
+#[cfg(feature = "locales")]
+pub use self::data::{
+    StaticLocale, DE_DE, ES_ES, FR_FR, JA_JP, JA_JP_ALPHABETIC_ERAS, KO_KR, PT_BR, ZH_CN,
+};
+
+/// Names and formats that `strftime`/`strptime` look up with `nl_langinfo`.
+///
+/// Formats returned by `d_t_fmt` and others are expanded recursively, so they can contain any
+/// conversion specifier, including those of the other formats. A format that ends up expanding
+/// into itself results in an error.
+pub trait Locale {
+    /// `ABDAY_*`, for `%a`.
+    fn weekday_short(&self, weekday: Weekday) -> &str;
+    /// `DAY_*`, for `%A`.
+    fn weekday_long(&self, weekday: Weekday) -> &str;
+    /// `ABMON_*`, for `%b` and `%h`.
+    fn month_short(&self, month: Month) -> &str;
+    /// `MON_*`, for `%B`.
+    fn month_long(&self, month: Month) -> &str;
+    /// The genitive form of the month name, like "marca" for "marzec" in Polish.
+    ///
+    /// Only used in parsing, where `%b` and `%B` accept any of the month names.
+    #[inline]
+    fn month_long_genitive(&self, month: Month) -> &str {
+        self.month_long(month)
+    }
+    /// The genitive form of the abbreviated month name. Only used in parsing.
+    #[inline]
+    fn month_short_genitive(&self, month: Month) -> &str {
+        self.month_short(month)
+    }
+    /// `AM_STR`, for `%p`. `%P` is the lower case of it.
+    fn am(&self) -> &str;
+    /// `PM_STR`, for `%p`. `%P` is the lower case of it.
+    fn pm(&self) -> &str;
+    /// `D_T_FMT`, for `%c`.
+    fn d_t_fmt(&self) -> &str;
+    /// `D_FMT`, for `%x`.
+    fn d_fmt(&self) -> &str;
+    /// `T_FMT`, for `%X`.
+    fn t_fmt(&self) -> &str;
+    /// `T_FMT_AMPM`, for `%r`.
+    fn t_fmt_ampm(&self) -> &str;
+    /// `ERA`, for `%EC`, `%Ey` and `%EY`. Sorted by `Era::start`, and empty if the locale has
+    /// no eras, in which case those fall back to `%C`, `%y` and `%Y`.
+    #[inline]
+    fn eras(&self) -> &[Era<'_>] {
+        &[]
+    }
+    /// `ALT_DIGITS`, for `%O` modifiers. `None` if the locale has none, in which case those fall
+    /// back to the ASCII digits.
+    #[inline]
+    fn alt_digits(&self) -> Option<AltDigits<'_>> {
+        None
+    }
+    /// `ERA_D_T_FMT`, for `%Ec`.
+    #[inline]
+    fn era_d_t_fmt(&self) -> &str {
+        self.d_t_fmt()
+    }
+    /// `ERA_D_FMT`, for `%Ex`.
+    #[inline]
+    fn era_d_fmt(&self) -> &str {
+        self.d_fmt()
+    }
+    /// `ERA_T_FMT`, for `%EX`.
+    #[inline]
+    fn era_t_fmt(&self) -> &str {
+        self.t_fmt()
+    }
+    /// The calendar of `%Y`, `%m`, `%d`, `%j`, `%B` and others. The names of the months are the
+    /// calendar's if it has them.
+    #[inline]
+    fn calendar(&self) -> &dyn Calendar {
+        &Gregorian
+    }
+}
+
+/// An entry of `ERA`, years counted from `start` until the start of the next entry.
+///
+/// The years are those of the calendar of the locale, the Gregorian one unless `Locale::calendar`
+/// says otherwise. The year `start` is in is `offset`, and the next one begins on the new year's
+/// day of the calendar, not on the anniversary of `start`.
+///
+/// Like glibc, the first year of an era that is written differently, as `元年` in Japanese, is
+/// an entry of its own, followed by an entry that starts on the next new year's day with `offset`
+/// 2.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Era<'a> {
+    /// The first day of this entry.
+    pub start: Date,
+    /// The year in the era `start` is in.
+    pub offset: i32,
+    /// The name of the era, for `%EC`.
+    pub name: &'a str,
+    /// The format of the year for `%EY`, like `%EC%Ey年`.
+    pub format: &'a str,
+}
+
+impl<'a> Era<'a> {
+    /// The year in the era `date` is in, for `%Ey`, counting the years of `calendar` from the
+    /// one `start` is in.
+    #[inline]
+    pub fn year_of(&self, date: Date, calendar: &dyn Calendar) -> i32 {
+        let (year, _, _) = calendar.calendar_date(date);
+        let (start, _, _) = calendar.calendar_date(self.start);
+        year - start + self.offset
+    }
+
+    /// The year of `calendar` the year `year_of_era` in the era is.
+    #[inline]
+    pub fn to_year(&self, year_of_era: i32, calendar: &dyn Calendar) -> i32 {
+        let (start, _, _) = calendar.calendar_date(self.start);
+        start - self.offset + year_of_era
+    }
+
+    /// The entry that `date` is in, if any.
+    pub fn find(eras: &'a [Self], date: Date) -> Option<&'a Self> {
+        eras.iter().rev().find(|era| era.start <= date)
+    }
+
+    /// The only era `eras` have, if all the entries have the same name.
+    pub(crate) fn sole(eras: &'a [Self]) -> Option<&'a Self> {
+        let first = eras.first()?;
+        eras.iter()
+            .all(|era| era.name == first.name)
+            .then_some(first)
+    }
+}
+
+impl Era<'static> {
+    /// The Buddhist era of Thailand, where years are the Gregorian ones plus 543, as in `th_TH`
+    /// of glibc.
+    pub const BUDDHIST: &'static [Self] = &[Era {
+        start: date(-542, Month::January, 1),
+        offset: 1,
+        name: "พ.ศ.",
+        format: "%EC %Ey",
+    }];
+
+    /// The Minguo era of Taiwan, where years are the Gregorian ones minus 1911, as in `zh_TW` of
+    /// glibc. Years before it are not supported.
+    pub const MINGUO: &'static [Self] = &[
+        Era {
+            start: date(1912, Month::January, 1),
+            offset: 1,
+            name: "民國",
+            format: "%EC元年",
+        },
+        Era {
+            start: date(1913, Month::January, 1),
+            offset: 2,
+            name: "民國",
+            format: "%EC%Ey年",
+        },
+    ];
+}
+
+/// A locale with its eras replaced, to number years differently, like with `Era::BUDDHIST`.
+///
+/// ```
+/// use time::macros::datetime;
+/// use time_fmt::{
+///     format::{format_date_time_with_locale, FormatOptions},
+///     locale::{Era, PosixLocale, WithEras},
+///     parse::{parse_date_time_maybe_with_zone_with_locale, ParseOptions},
+/// };
+///
+/// let locale = WithEras::new(&PosixLocale, Era::BUDDHIST);
+/// let dt = datetime!(2022-03-06 00:00);
+/// assert_eq!(
+///     format_date_time_with_locale("%Ey-%m-%d", dt, &locale, &FormatOptions::default()).unwrap(),
+///     "2565-03-06"
+/// );
+/// assert_eq!(
+///     parse_date_time_maybe_with_zone_with_locale("%Ey-%m-%d", "2565-03-06", &locale, &ParseOptions::default()).unwrap(),
+///     (dt, None)
+/// );
+/// ```
+#[derive(Clone, Copy)]
+pub struct WithEras<'a> {
+    locale: &'a dyn Locale,
+    eras: &'a [Era<'a>],
+}
+
+impl<'a> WithEras<'a> {
+    /// `locale` but with `eras`, which should be sorted by `Era::start`.
+    pub fn new(locale: &'a dyn Locale, eras: &'a [Era<'a>]) -> Self {
+        Self { locale, eras }
+    }
+}
+
+/// Implements `Locale` for an adapter of `self.locale`, delegating all but the given methods.
+macro_rules! delegate_locale {
+    ($ty:ident, { $($overridden:item)* }) => {
+        impl<'a> Locale for $ty<'a> {
+            #[inline]
+            fn weekday_short(&self, weekday: Weekday) -> &str {
+                self.locale.weekday_short(weekday)
+            }
+
+            #[inline]
+            fn weekday_long(&self, weekday: Weekday) -> &str {
+                self.locale.weekday_long(weekday)
+            }
+
+            #[inline]
+            fn month_short(&self, month: Month) -> &str {
+                self.locale.month_short(month)
+            }
+
+            #[inline]
+            fn month_long(&self, month: Month) -> &str {
+                self.locale.month_long(month)
+            }
+
+            #[inline]
+            fn month_long_genitive(&self, month: Month) -> &str {
+                self.locale.month_long_genitive(month)
+            }
+
+            #[inline]
+            fn month_short_genitive(&self, month: Month) -> &str {
+                self.locale.month_short_genitive(month)
+            }
+
+            #[inline]
+            fn am(&self) -> &str {
+                self.locale.am()
+            }
+
+            #[inline]
+            fn pm(&self) -> &str {
+                self.locale.pm()
+            }
+
+            #[inline]
+            fn d_t_fmt(&self) -> &str {
+                self.locale.d_t_fmt()
+            }
+
+            #[inline]
+            fn d_fmt(&self) -> &str {
+                self.locale.d_fmt()
+            }
+
+            #[inline]
+            fn t_fmt(&self) -> &str {
+                self.locale.t_fmt()
+            }
+
+            #[inline]
+            fn t_fmt_ampm(&self) -> &str {
+                self.locale.t_fmt_ampm()
+            }
+
+            #[inline]
+            fn alt_digits(&self) -> Option<AltDigits<'_>> {
+                self.locale.alt_digits()
+            }
+
+            #[inline]
+            fn era_d_t_fmt(&self) -> &str {
+                self.locale.era_d_t_fmt()
+            }
+
+            #[inline]
+            fn era_d_fmt(&self) -> &str {
+                self.locale.era_d_fmt()
+            }
+
+            #[inline]
+            fn era_t_fmt(&self) -> &str {
+                self.locale.era_t_fmt()
+            }
+
+            $($overridden)*
+        }
+    };
+}
+
+delegate_locale!(WithEras, {
+    #[inline]
+    fn eras(&self) -> &[Era<'_>] {
+        self.eras
+    }
+
+    #[inline]
+    fn calendar(&self) -> &dyn Calendar {
+        self.locale.calendar()
+    }
+});
+
+/// A locale with its calendar replaced, like with `SolarHijri`.
+///
+/// ```
+/// use time::macros::datetime;
+/// use time_fmt::{
+///     calendar::SolarHijri,
+///     format::{format_date_time_with_locale, FormatOptions},
+///     locale::{PosixLocale, WithCalendar},
+///     parse::{parse_date_time_maybe_with_zone_with_locale, ParseOptions},
+/// };
+///
+/// let locale = WithCalendar::new(&PosixLocale, &SolarHijri);
+/// let dt = datetime!(2022-03-06 00:00);
+/// assert_eq!(
+///     format_date_time_with_locale("%Y/%m/%d", dt, &locale, &FormatOptions::default()).unwrap(),
+///     "1400/12/15"
+/// );
+/// assert_eq!(
+///     parse_date_time_maybe_with_zone_with_locale("%Y/%m/%d", "1400/12/15", &locale, &ParseOptions::default()).unwrap(),
+///     (dt, None)
+/// );
+/// ```
+#[derive(Clone, Copy)]
+pub struct WithCalendar<'a> {
+    locale: &'a dyn Locale,
+    calendar: &'a dyn Calendar,
+}
+
+impl<'a> WithCalendar<'a> {
+    /// `locale` but in `calendar`.
+    pub fn new(locale: &'a dyn Locale, calendar: &'a dyn Calendar) -> Self {
+        Self { locale, calendar }
+    }
+}
+
+delegate_locale!(WithCalendar, {
+    #[inline]
+    fn eras(&self) -> &[Era<'_>] {
+        self.locale.eras()
+    }
+
+    #[inline]
+    fn calendar(&self) -> &dyn Calendar {
+        self.calendar
+    }
+});
+
+/// The C/POSIX locale, which is what the functions without a locale use.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PosixLocale;
+
+impl Locale for PosixLocale {
+    #[inline]
+    fn weekday_short(&self, weekday: Weekday) -> &str {
+        util::weekday_short_str(weekday)
+    }
+
+    #[inline]
+    fn weekday_long(&self, weekday: Weekday) -> &str {
+        util::weekday_long_str(weekday)
+    }
+
+    #[inline]
+    fn month_short(&self, month: Month) -> &str {
+        util::month_short_str(month)
+    }
+
+    #[inline]
+    fn month_long(&self, month: Month) -> &str {
+        util::month_long_str(month)
+    }
+
+    #[inline]
+    fn am(&self) -> &str {
+        util::ampm_upper(0)
+    }
+
+    #[inline]
+    fn pm(&self) -> &str {
+        util::ampm_upper(12)
+    }
+
+    #[inline]
+    fn d_t_fmt(&self) -> &str {
+        "%a %b %e %H:%M:%S %Y"
+    }
+
+    #[inline]
+    fn d_fmt(&self) -> &str {
+        "%m/%d/%y"
+    }
+
+    #[inline]
+    fn t_fmt(&self) -> &str {
+        "%H:%M:%S"
+    }
+
+    #[inline]
+    fn t_fmt_ampm(&self) -> &str {
+        "%I:%M:%S %p"
+    }
+}
+
+/// One of the formats a locale provides.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum LocaleFormat {
+    /// `%c`.
+    DateTime,
+    /// `%x`.
+    Date,
+    /// `%X`.
+    Time,
+    /// `%r`.
+    TimeAmPm,
+    /// `%Ec`.
+    EraDateTime,
+    /// `%Ex`.
+    EraDate,
+    /// `%EX`.
+    EraTime,
+}
+
+impl LocaleFormat {
+    #[inline]
+    pub(crate) fn get(self, locale: &dyn Locale) -> &str {
+        match self {
+            Self::DateTime => locale.d_t_fmt(),
+            Self::Date => locale.d_fmt(),
+            Self::Time => locale.t_fmt(),
+            Self::TimeAmPm => locale.t_fmt_ampm(),
+            Self::EraDateTime => locale.era_d_t_fmt(),
+            Self::EraDate => locale.era_d_fmt(),
+            Self::EraTime => locale.era_t_fmt(),
+        }
+    }
+
+    /// `%c`, or `%Ec` if `era` is true.
+    #[inline]
+    pub(crate) const fn date_time(era: bool) -> Self {
+        if era {
+            Self::EraDateTime
+        } else {
+            Self::DateTime
+        }
+    }
+
+    /// `%x`, or `%Ex` if `era` is true.
+    #[inline]
+    pub(crate) const fn date(era: bool) -> Self {
+        if era {
+            Self::EraDate
+        } else {
+            Self::Date
+        }
+    }
+
+    /// `%X`, or `%EX` if `era` is true.
+    #[inline]
+    pub(crate) const fn time(era: bool) -> Self {
+        if era {
+            Self::EraTime
+        } else {
+            Self::Time
+        }
+    }
+}
+
+/// Formats being expanded, to detect a format that expands into itself.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Expanding(u8);
+
+impl Expanding {
+    /// The bit for the format of an era year, which isn't a `LocaleFormat` as it depends on the
+    /// era.
+    const ERA_YEAR: u8 = 1 << 7;
+
+    #[inline]
+    fn enter_bit(self, bit: u8) -> Option<Self> {
+        if self.0 & bit == 0 {
+            Some(Self(self.0 | bit))
+        } else {
+            None
+        }
+    }
+
+    /// Marks `format` as being expanded, or returns `None` if it already is.
+    #[inline]
+    pub(crate) fn enter(self, format: LocaleFormat) -> Option<Self> {
+        self.enter_bit(1 << format as u8)
+    }
+
+    /// Marks `Era::format` as being expanded, or returns `None` if it already is.
+    #[inline]
+    pub(crate) fn enter_era_year(self) -> Option<Self> {
+        self.enter_bit(Self::ERA_YEAR)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::{
+        macros::{datetime, offset},
+        Month, PrimitiveDateTime, Weekday,
+    };
+
+    use super::{AltDigits, Era, Locale, PosixLocale, WithCalendar, WithEras};
+    use crate::{
+        calendar::{Calendar, Gregorian, Julian, SolarHijri},
+        format::{
+            format_date_time, format_date_time_with_locale, format_zoned_date_time_with_locale,
+            FormatError, FormatOptions, Strftime, StrftimeFormat,
+        },
+        parse::{
+            parse_date_time_maybe_with_zone, parse_date_time_maybe_with_zone_with_locale,
+            parse_strict_date_time_maybe_with_zone_with_locale, ParseError, ParseOptions,
+            StrptimeFormat,
+        },
+    };
+
+    /// Something like `de_DE`, with a replaceable `%r`.
+    struct TestLocale {
+        t_fmt_ampm: &'static str,
+        alt_digits: Option<AltDigits<'static>>,
+    }
+
+    impl Locale for TestLocale {
+        fn weekday_short(&self, weekday: Weekday) -> &str {
+            ["Mo", "Di", "Mi", "Do", "Fr", "Sa", "So"][weekday.number_days_from_monday() as usize]
+        }
+        fn weekday_long(&self, weekday: Weekday) -> &str {
+            [
+                "Montag",
+                "Dienstag",
+                "Mittwoch",
+                "Donnerstag",
+                "Freitag",
+                "Samstag",
+                "Sonntag",
+            ][weekday.number_days_from_monday() as usize]
+        }
+        fn month_short(&self, month: Month) -> &str {
+            if month == Month::March {
+                "Mär"
+            } else {
+                PosixLocale.month_short(month)
+            }
+        }
+        fn month_long(&self, month: Month) -> &str {
+            if month == Month::March {
+                "März"
+            } else {
+                PosixLocale.month_long(month)
+            }
+        }
+        fn month_long_genitive(&self, month: Month) -> &str {
+            if month == Month::March {
+                "Märzens"
+            } else {
+                self.month_long(month)
+            }
+        }
+        fn am(&self) -> &str {
+            "vorm."
+        }
+        fn pm(&self) -> &str {
+            "nachm."
+        }
+        fn d_t_fmt(&self) -> &str {
+            "%a %d %b %Y %T %Z"
+        }
+        fn d_fmt(&self) -> &str {
+            "%d.%m.%Y"
+        }
+        fn t_fmt(&self) -> &str {
+            "%T"
+        }
+        fn t_fmt_ampm(&self) -> &str {
+            self.t_fmt_ampm
+        }
+        fn alt_digits(&self) -> Option<AltDigits<'_>> {
+            self.alt_digits
+        }
+    }
+
+    const LOCALE: TestLocale = TestLocale {
+        t_fmt_ampm: "%I.%M.%S %p",
+        alt_digits: None,
+    };
+
+    #[test]
+    fn test_posix() -> Result<(), FormatError> {
+        let dt = datetime!(2022-03-06 12:34:56);
+        for fmt in ["%a %A %b %B %p %P", "%c|%x|%X|%r", "%^c %#p"] {
+            assert_eq!(
+                format_date_time_with_locale(fmt, dt, &PosixLocale, &FormatOptions::default())?,
+                format_date_time(fmt, dt)?
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_format() -> Result<(), FormatError> {
+        let dt = datetime!(2022-03-06 12:34:56);
+        assert_eq!(
+            format_zoned_date_time_with_locale(
+                "%c",
+                dt,
+                offset!(+1:00),
+                "MEZ",
+                &LOCALE,
+                &FormatOptions::default()
+            )?,
+            "So 06 Mär 2022 12:34:56 MEZ"
+        );
+        assert_eq!(
+            format_date_time_with_locale("%A %B %x %r %P", dt, &LOCALE, &FormatOptions::default())?,
+            "Sonntag März 06.03.2022 12.34.56 nachm. nachm."
+        );
+        assert_eq!(
+            format_date_time_with_locale("%^c|%^r", dt, &LOCALE, &FormatOptions::default())?,
+            "SO 06 MÄR 2022 12:34:56 |12.34.56 NACHM."
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse() -> Result<(), ParseError> {
+        assert_eq!(
+            parse_date_time_maybe_with_zone_with_locale(
+                "%c",
+                "Sonntag 06 mär 2022 12:34:56 MEZ",
+                &LOCALE,
+                &ParseOptions::default()
+            )?,
+            (
+                datetime!(2022-03-06 12:34:56),
+                Some(crate::parse::TimeZoneSpecifier::Name("MEZ"))
+            )
+        );
+        assert_eq!(
+            parse_strict_date_time_maybe_with_zone_with_locale(
+                "%x %r",
+                "6.3.2022 12.34.56 Vorm.",
+                &LOCALE,
+                &ParseOptions::default()
+            )?,
+            (datetime!(2022-03-06 00:34:56), None)
+        );
+        assert_eq!(
+            parse_date_time_maybe_with_zone("%b", "Mär"),
+            Err(ParseError::NotMatch("month name"))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_names() -> Result<(), ParseError> {
+        fn parse(fmt: &str, s: &str) -> Result<PrimitiveDateTime, ParseError> {
+            parse_strict_date_time_maybe_with_zone_with_locale(
+                fmt,
+                s,
+                &LOCALE,
+                &ParseOptions::default(),
+            )
+            .map(|(dt, _)| dt)
+        }
+        for s in ["MÄRZ", "märz", "Mär", "mÄr", "Mär.", "Märzens"] {
+            assert_eq!(
+                parse("%B %Y", &format!("{} 2022", s))?,
+                datetime!(2022-03-01 00:00),
+                "{}",
+                s
+            );
+        }
+        for s in ["sonntag", "SONNTAG", "So", "so."] {
+            assert_eq!(
+                parse("%a %Y", &format!("{} 2022", s))?,
+                datetime!(2022-01-01 00:00)
+            );
+        }
+        for s in ["nachm.", "NACHM.", "nachm"] {
+            assert_eq!(
+                parse("%I %p", &format!("1 {}", s))?,
+                datetime!(1900-01-01 13:00)
+            );
+        }
+        assert_eq!(
+            parse("%I %p.", "1 nachm."),
+            Err(ParseError::NotMatch("string literal"))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_recursive() {
+        let dt = datetime!(2022-03-06 12:34:56);
+        let locale = TestLocale {
+            t_fmt_ampm: "%H %c %r",
+            alt_digits: None,
+        };
+        assert_eq!(
+            format_date_time_with_locale("%r", dt, &locale, &FormatOptions::default()),
+            Err(FormatError::RecursiveLocaleFormat)
+        );
+        assert_eq!(
+            parse_date_time_maybe_with_zone_with_locale(
+                "%r",
+                "12 So 06 Mär 2022 12:34:56 ",
+                &locale,
+                &ParseOptions::default()
+            ),
+            Err(ParseError::RecursiveLocaleFormat)
+        );
+    }
+
+    #[test]
+    fn test_alt_digits() -> Result<(), Box<dyn std::error::Error>> {
+        let dt = datetime!(2022-03-06 07:04:56.078);
+        let fmt = "%OY/%Om/%Od %OH:%OM:%OS.%3Of";
+        for (alt_digits, expected) in [
+            (None, "2022/03/06 07:04:56.078  6 7 09"),
+            (
+                Some(AltDigits::FULLWIDTH),
+                "２０２２/０３/０６ ０７:０４:５６.０７８  ６ ７ ０９",
+            ),
+            (
+                Some(AltDigits::ARABIC_INDIC),
+                "٢٠٢٢/٠٣/٠٦ ٠٧:٠٤:٥٦.٠٧٨  ٦ ٧ ٠٩",
+            ),
+            (
+                Some(AltDigits::DEVANAGARI),
+                "२०२२/०३/०६ ०७:०४:५६.०७८  ६ ७ ०९",
+            ),
+            (Some(AltDigits::THAI), "๒๐๒๒/๐๓/๐๖ ๐๗:๐๔:๕๖.๐๗๘  ๖ ๗ ๐๙"),
+            (
+                Some(AltDigits::KANJI),
+                "2022/三/六 七:四:五十六.078 六 七 九",
+            ),
+        ] {
+            let locale = TestLocale {
+                alt_digits,
+                ..LOCALE
+            };
+            assert_eq!(
+                format_date_time_with_locale(
+                    &format!("{} %_Oe %Ou %OV", fmt),
+                    dt,
+                    &locale,
+                    &FormatOptions::default()
+                )?,
+                expected
+            );
+            let s = format_date_time_with_locale(fmt, dt, &locale, &FormatOptions::default())?;
+            assert_eq!(
+                parse_strict_date_time_maybe_with_zone_with_locale(
+                    fmt,
+                    &s,
+                    &locale,
+                    &ParseOptions::default()
+                )?,
+                (dt, None)
+            );
+            // Without `%O`, only the ASCII digits.
+            assert_eq!(
+                format_date_time_with_locale("%d", dt, &locale, &FormatOptions::default())?,
+                "06"
+            );
+        }
+        let locale = TestLocale {
+            alt_digits: Some(AltDigits::KANJI),
+            ..LOCALE
+        };
+        // Mixed with the ASCII ones, and the longest match.
+        assert_eq!(
+            parse_strict_date_time_maybe_with_zone_with_locale(
+                "%Od %Om %OY",
+                "十二 11 2022",
+                &locale,
+                &ParseOptions::default()
+            )?,
+            (datetime!(2022-11-12 00:00), None)
+        );
+        assert_eq!(
+            parse_strict_date_time_maybe_with_zone_with_locale(
+                "%d",
+                "十二",
+                &locale,
+                &ParseOptions::default()
+            ),
+            Err(ParseError::UnexpectedByte("digits", 0xe5))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_compiled() -> Result<(), Box<dyn std::error::Error>> {
+        let dt = datetime!(2022-03-06 07:04:56);
+        let locale = TestLocale {
+            alt_digits: Some(AltDigits::FULLWIDTH),
+            ..LOCALE
+        };
+        let locale = WithEras::new(&locale, Era::BUDDHIST);
+        for (fmt, expected) in [
+            ("%c|%^r", "So 06 Mär 2022 07:04:56 |07.04.56 VORM."),
+            (
+                "%Ec|%EY|%Od %OH",
+                "So 06 Mär 2022 07:04:56 |พ.ศ. 2565|０６ ０７",
+            ),
+            ("%Ey-%Om-%Od %A", "2565-０３-０６ Sonntag"),
+        ] {
+            assert_eq!(
+                format_date_time_with_locale(fmt, dt, &locale, &FormatOptions::default())?,
+                expected
+            );
+            let format = StrftimeFormat::new_with_locale(fmt, &locale)?;
+            assert_eq!(format.format_date_time(dt)?, expected);
+            assert_eq!(
+                Strftime::date_time(fmt, dt)
+                    .with_locale(&locale)
+                    .to_string(),
+                expected
+            );
+        }
+        for (fmt, s) in [
+            ("%x %r", "06.03.2022 07.04.56 vorm."),
+            ("%Ey-%Om-%Od %OH:%OM:%OS", "2565-０３-０６ ０７:０４:５６"),
+        ] {
+            let format = StrptimeFormat::new_with_locale(fmt, &locale)?;
+            assert_eq!(format.parse_strict(s)?, (dt, None));
+            assert_eq!(
+                parse_strict_date_time_maybe_with_zone_with_locale(
+                    fmt,
+                    s,
+                    &locale,
+                    &ParseOptions::default()
+                )?,
+                (dt, None)
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_eras() -> Result<(), Box<dyn std::error::Error>> {
+        let buddhist = WithEras::new(&LOCALE, Era::BUDDHIST);
+        let minguo = WithEras::new(&PosixLocale, Era::MINGUO);
+        for (locale, dt, fmt, expected) in [
+            (
+                &buddhist,
+                datetime!(2022-03-06 00:00),
+                "%Ey-%m-%d",
+                "2565-03-06",
+            ),
+            (
+                &buddhist,
+                datetime!(2022-03-01 00:00),
+                "%EY %B",
+                "พ.ศ. 2565 März",
+            ),
+            (
+                &buddhist,
+                datetime!(0001-01-01 00:00),
+                "%EC|%Ey|%EY",
+                "พ.ศ.|544|พ.ศ. 544",
+            ),
+            (
+                &minguo,
+                datetime!(2022-03-06 00:00),
+                "%EC %Ey/%m/%d",
+                "民國 111/03/06",
+            ),
+            (
+                &minguo,
+                datetime!(1913-01-01 00:00),
+                "%EY%m月%d日",
+                "民國2年01月01日",
+            ),
+            (
+                &minguo,
+                datetime!(1912-12-31 00:00),
+                "%EY%m月%d日",
+                "民國元年12月31日",
+            ),
+            (
+                &minguo,
+                datetime!(1911-12-31 00:00),
+                "%EY%m月%d日",
+                "191112月31日",
+            ),
+        ] {
+            assert_eq!(
+                format_date_time_with_locale(fmt, dt, locale, &FormatOptions::default())?,
+                expected
+            );
+            assert_eq!(
+                parse_strict_date_time_maybe_with_zone_with_locale(
+                    fmt,
+                    expected,
+                    locale,
+                    &ParseOptions::default()
+                )?,
+                (dt, None)
+            );
+        }
+        // Names and others are still of the original locale.
+        assert_eq!(
+            format_date_time_with_locale(
+                "%Ex %A",
+                datetime!(2022-03-06 00:00),
+                &buddhist,
+                &FormatOptions::default()
+            )?,
+            "06.03.2022 Sonntag"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_calendar() -> Result<(), Box<dyn std::error::Error>> {
+        let locale = WithCalendar::new(&LOCALE, &SolarHijri);
+        let dt = datetime!(2022-03-06 12:34:56);
+        for (fmt, expected) in [
+            ("%Y/%m/%d", "1400/12/15"),
+            ("%C %y %j", "14 00 351"),
+            ("%A %e %B %Y", "Sonntag 15 اسفند 1400"),
+            ("%x", "15.12.1400"),
+        ] {
+            assert_eq!(
+                format_date_time_with_locale(fmt, dt, &locale, &FormatOptions::default())?,
+                expected
+            );
+            assert_eq!(
+                parse_strict_date_time_maybe_with_zone_with_locale(
+                    &format!("{} %T", fmt),
+                    &format!("{} 12:34:56", expected),
+                    &locale,
+                    &ParseOptions::default()
+                )?,
+                (dt, None)
+            );
+        }
+        // Weeks are the same in any calendar.
+        assert_eq!(
+            format_date_time_with_locale("%G-W%V-%u %U", dt, &locale, &FormatOptions::default())?,
+            "2022-W09-7 10"
+        );
+        // Without `%G`, the ISO 8601 week-based year overlapping the year of the calendar.
+        for (fmt, s) in [("%G-W%V-%u", "2022-W09-7"), ("%Y W%V-%u", "1400 W09-7")] {
+            assert_eq!(
+                parse_strict_date_time_maybe_with_zone_with_locale(
+                    fmt,
+                    s,
+                    &locale,
+                    &ParseOptions::default()
+                )?,
+                (datetime!(2022-03-06 00:00), None)
+            );
+        }
+        assert_eq!(
+            parse_strict_date_time_maybe_with_zone_with_locale(
+                "%Y W%V",
+                "1400 W01",
+                &locale,
+                &ParseOptions::default()
+            )?,
+            (datetime!(2022-01-03 00:00), None)
+        );
+        // So are `%U` and `%W`, of the Gregorian year overlapping the year of the calendar.
+        for (fmt, s, dt) in [
+            ("%Y %U %a", "1400 10 So", datetime!(2022-03-06 00:00)),
+            ("%Y %W %a", "1400 09 So", datetime!(2022-03-06 00:00)),
+            ("%Y %U %a", "1400 20 So", datetime!(2021-05-16 00:00)),
+        ] {
+            assert_eq!(
+                format_date_time_with_locale(fmt, dt, &locale, &FormatOptions::default())?,
+                s
+            );
+            assert_eq!(
+                parse_strict_date_time_maybe_with_zone_with_locale(
+                    fmt,
+                    s,
+                    &locale,
+                    &ParseOptions::default()
+                )?,
+                (dt, None)
+            );
+        }
+        assert_eq!(
+            parse_strict_date_time_maybe_with_zone_with_locale(
+                "%m/%d",
+                "12/15",
+                &locale,
+                &ParseOptions::default()
+            )?,
+            (datetime!(1900-03-06 00:00), None)
+        );
+        assert_eq!(
+            parse_strict_date_time_maybe_with_zone_with_locale(
+                "%Y/%m/%d",
+                "1400/12/30",
+                &locale,
+                &ParseOptions::default()
+            ),
+            Err(ParseError::ComponentOutOfRange("date"))
+        );
+
+        let locale = WithCalendar::new(&PosixLocale, &Julian::GREGORIAN_REFORM);
+        for (dt, expected) in [
+            (datetime!(1582-10-14 00:00), "Thu Oct  4 00:00:00 1582 277"),
+            (datetime!(1582-10-15 00:00), "Fri Oct 15 00:00:00 1582 278"),
+            (datetime!(1500-03-10 00:00), "Sat Feb 29 00:00:00 1500 060"),
+        ] {
+            assert_eq!(
+                format_date_time_with_locale("%c %j", dt, &locale, &FormatOptions::default())?,
+                expected
+            );
+            assert_eq!(
+                parse_strict_date_time_maybe_with_zone_with_locale(
+                    "%c %j",
+                    expected,
+                    &locale,
+                    &ParseOptions::default()
+                )?,
+                (dt, None)
+            );
+        }
+        assert_eq!(
+            parse_strict_date_time_maybe_with_zone_with_locale(
+                "%F",
+                "1582-10-10",
+                &locale,
+                &ParseOptions::default()
+            ),
+            Err(ParseError::ComponentOutOfRange("date"))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_era_years() {
+        let era = Era {
+            start: datetime!(2019-05-01 00:00).date(),
+            offset: 1,
+            name: "R",
+            format: "%EC%Ey",
+        };
+        for (calendar, dt, year, year_of_era) in [
+            (
+                &Gregorian as &dyn Calendar,
+                datetime!(2019-12-31 00:00),
+                2019,
+                1,
+            ),
+            (&Gregorian, datetime!(2020-01-01 00:00), 2020, 2),
+            (&SolarHijri, datetime!(2020-03-19 00:00), 1398, 1),
+            (&SolarHijri, datetime!(2020-03-20 00:00), 1399, 2),
+        ] {
+            assert_eq!(era.year_of(dt.date(), calendar), year_of_era);
+            assert_eq!(era.to_year(year_of_era, calendar), year);
+        }
+    }
+
+    #[test]
+    fn test_eras_in_calendar() -> Result<(), Box<dyn std::error::Error>> {
+        // Years of an era count those of the calendar, here from Nowruz to Nowruz.
+        let eras = [Era {
+            start: SolarHijri
+                .to_date(1, Month::January, 1)
+                .ok_or("no such date")?,
+            offset: 1,
+            name: "AP",
+            format: "%Ey %EC",
+        }];
+        let calendar = WithCalendar::new(&PosixLocale, &SolarHijri);
+        let locale = WithEras::new(&calendar, &eras);
+        for (dt, expected) in [
+            (datetime!(2022-03-06 00:00), "1400 AP/12/15"),
+            (datetime!(2022-03-21 00:00), "1401 AP/01/01"),
+        ] {
+            assert_eq!(
+                format_date_time_with_locale("%EY/%m/%d", dt, &locale, &FormatOptions::default())?,
+                expected
+            );
+            assert_eq!(
+                parse_strict_date_time_maybe_with_zone_with_locale(
+                    "%EY/%m/%d",
+                    expected,
+                    &locale,
+                    &ParseOptions::default()
+                )?,
+                (dt, None)
+            );
+        }
+        Ok(())
+    }
+}
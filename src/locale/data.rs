@@ -0,0 +1,754 @@
+use time::{Date, Month, Weekday};
+
+use super::{AltDigits, Era, Locale};
+use crate::util::date;
+
+/// A locale whose names and formats are compiled into the binary, following glibc's locale
+/// data.
+///
+/// ```
+/// use time::macros::datetime;
+/// use time_fmt::{
+///     format::{format_date_time_with_locale, FormatOptions},
+///     locale::JA_JP,
+/// };
+///
+/// let dt = datetime!(2022-03-06 12:34:56);
+/// assert_eq!(
+///     format_date_time_with_locale("%c (%a)", dt, &JA_JP, &FormatOptions::default()).unwrap(),
+///     "2022年03月06日 12時34分56秒 (日)"
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StaticLocale {
+    name: &'static str,
+    /// From Monday to Sunday.
+    weekday_short: [&'static str; 7],
+    /// From Monday to Sunday.
+    weekday_long: [&'static str; 7],
+    month_short: [&'static str; 12],
+    month_long: [&'static str; 12],
+    am_pm: [&'static str; 2],
+    d_t_fmt: &'static str,
+    d_fmt: &'static str,
+    t_fmt: &'static str,
+    t_fmt_ampm: &'static str,
+    alt_digits: Option<AltDigits<'static>>,
+    eras: &'static [Era<'static>],
+    era_d_t_fmt: &'static str,
+    era_d_fmt: &'static str,
+    era_t_fmt: &'static str,
+}
+
+impl StaticLocale {
+    /// All the bundled locales.
+    pub const ALL: [&'static Self; 7] = [&DE_DE, &ES_ES, &FR_FR, &JA_JP, &KO_KR, &PT_BR, &ZH_CN];
+
+    /// The name of the locale, like `ja_JP`.
+    #[inline]
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Looks up a bundled locale by its name, like `ja_JP` or `ja_JP.UTF-8`.
+    pub fn from_name(name: &str) -> Option<&'static Self> {
+        let name = name.split_once('.').map_or(name, |(name, _codeset)| name);
+        Self::ALL.into_iter().find(|locale| locale.name == name)
+    }
+}
+
+impl Locale for StaticLocale {
+    #[inline]
+    fn weekday_short(&self, weekday: Weekday) -> &str {
+        self.weekday_short[weekday.number_days_from_monday() as usize]
+    }
+
+    #[inline]
+    fn weekday_long(&self, weekday: Weekday) -> &str {
+        self.weekday_long[weekday.number_days_from_monday() as usize]
+    }
+
+    #[inline]
+    fn month_short(&self, month: Month) -> &str {
+        self.month_short[month as usize - 1]
+    }
+
+    #[inline]
+    fn month_long(&self, month: Month) -> &str {
+        self.month_long[month as usize - 1]
+    }
+
+    #[inline]
+    fn am(&self) -> &str {
+        self.am_pm[0]
+    }
+
+    #[inline]
+    fn pm(&self) -> &str {
+        self.am_pm[1]
+    }
+
+    #[inline]
+    fn d_t_fmt(&self) -> &str {
+        self.d_t_fmt
+    }
+
+    #[inline]
+    fn d_fmt(&self) -> &str {
+        self.d_fmt
+    }
+
+    #[inline]
+    fn t_fmt(&self) -> &str {
+        self.t_fmt
+    }
+
+    #[inline]
+    fn t_fmt_ampm(&self) -> &str {
+        self.t_fmt_ampm
+    }
+
+    #[inline]
+    fn alt_digits(&self) -> Option<AltDigits<'_>> {
+        self.alt_digits
+    }
+
+    #[inline]
+    fn eras(&self) -> &[Era<'_>] {
+        self.eras
+    }
+
+    #[inline]
+    fn era_d_t_fmt(&self) -> &str {
+        self.era_d_t_fmt
+    }
+
+    #[inline]
+    fn era_d_fmt(&self) -> &str {
+        self.era_d_fmt
+    }
+
+    #[inline]
+    fn era_t_fmt(&self) -> &str {
+        self.era_t_fmt
+    }
+}
+
+/// The entries of a Japanese era starting on the given day, its first year `元年` and the rest.
+macro_rules! japanese_era {
+    ($name:literal, $year:literal, $month:ident, $day:literal) => {
+        [
+            Era {
+                start: date($year, Month::$month, $day),
+                offset: 1,
+                name: $name,
+                format: "%EC元年",
+            },
+            Era {
+                start: date($year + 1, Month::January, 1),
+                offset: 2,
+                name: $name,
+                format: "%EC%Ey年",
+            },
+        ]
+    };
+}
+
+/// Japanese eras since Meiji, with `元年` for their first years. The start of Meiji predates the
+/// adoption of the Gregorian calendar on 1873-01-01, and is given as its proleptic Gregorian date.
+const JAPANESE_ERAS: [Era<'static>; 10] = {
+    let [m0, m1] = japanese_era!("明治", 1868, October, 23);
+    let [t0, t1] = japanese_era!("大正", 1912, July, 30);
+    let [s0, s1] = japanese_era!("昭和", 1926, December, 25);
+    let [h0, h1] = japanese_era!("平成", 1989, January, 8);
+    let [r0, r1] = japanese_era!("令和", 2019, May, 1);
+    [m0, m1, t0, t1, s0, s1, h0, h1, r0, r1]
+};
+
+/// Japanese eras abbreviated to the first letter of their romanized names, like `R4`.
+const JAPANESE_ALPHABETIC_ERAS: [Era<'static>; 5] = {
+    const fn era(name: &'static str, start: Date) -> Era<'static> {
+        Era {
+            start,
+            offset: 1,
+            name,
+            format: "%EC%Ey",
+        }
+    }
+    [
+        era("M", date(1868, Month::October, 23)),
+        era("T", date(1912, Month::July, 30)),
+        era("S", date(1926, Month::December, 25)),
+        era("H", date(1989, Month::January, 8)),
+        era("R", date(2019, Month::May, 1)),
+    ]
+};
+
+/// German (Germany).
+pub const DE_DE: StaticLocale = StaticLocale {
+    name: "de_DE",
+    weekday_short: ["Mo", "Di", "Mi", "Do", "Fr", "Sa", "So"],
+    weekday_long: [
+        "Montag",
+        "Dienstag",
+        "Mittwoch",
+        "Donnerstag",
+        "Freitag",
+        "Samstag",
+        "Sonntag",
+    ],
+    month_short: [
+        "Jan", "Feb", "Mär", "Apr", "Mai", "Jun", "Jul", "Aug", "Sep", "Okt", "Nov", "Dez",
+    ],
+    month_long: [
+        "Januar",
+        "Februar",
+        "März",
+        "April",
+        "Mai",
+        "Juni",
+        "Juli",
+        "August",
+        "September",
+        "Oktober",
+        "November",
+        "Dezember",
+    ],
+    am_pm: ["", ""],
+    d_t_fmt: "%a %d %b %Y %T %Z",
+    d_fmt: "%d.%m.%Y",
+    t_fmt: "%T",
+    t_fmt_ampm: "",
+    alt_digits: None,
+    eras: &[],
+    era_d_t_fmt: "%a %d %b %Y %T %Z",
+    era_d_fmt: "%d.%m.%Y",
+    era_t_fmt: "%T",
+};
+
+/// Spanish (Spain).
+pub const ES_ES: StaticLocale = StaticLocale {
+    name: "es_ES",
+    weekday_short: ["lun", "mar", "mié", "jue", "vie", "sáb", "dom"],
+    weekday_long: [
+        "lunes",
+        "martes",
+        "miércoles",
+        "jueves",
+        "viernes",
+        "sábado",
+        "domingo",
+    ],
+    month_short: [
+        "ene", "feb", "mar", "abr", "may", "jun", "jul", "ago", "sep", "oct", "nov", "dic",
+    ],
+    month_long: [
+        "enero",
+        "febrero",
+        "marzo",
+        "abril",
+        "mayo",
+        "junio",
+        "julio",
+        "agosto",
+        "septiembre",
+        "octubre",
+        "noviembre",
+        "diciembre",
+    ],
+    am_pm: ["", ""],
+    d_t_fmt: "%a %d %b %Y %T %Z",
+    d_fmt: "%d/%m/%y",
+    t_fmt: "%T",
+    t_fmt_ampm: "",
+    alt_digits: None,
+    eras: &[],
+    era_d_t_fmt: "%a %d %b %Y %T %Z",
+    era_d_fmt: "%d/%m/%y",
+    era_t_fmt: "%T",
+};
+
+/// French (France).
+pub const FR_FR: StaticLocale = StaticLocale {
+    name: "fr_FR",
+    weekday_short: ["lun.", "mar.", "mer.", "jeu.", "ven.", "sam.", "dim."],
+    weekday_long: [
+        "lundi", "mardi", "mercredi", "jeudi", "vendredi", "samedi", "dimanche",
+    ],
+    month_short: [
+        "janv.", "févr.", "mars", "avril", "mai", "juin", "juil.", "août", "sept.", "oct.", "nov.",
+        "déc.",
+    ],
+    month_long: [
+        "janvier",
+        "février",
+        "mars",
+        "avril",
+        "mai",
+        "juin",
+        "juillet",
+        "août",
+        "septembre",
+        "octobre",
+        "novembre",
+        "décembre",
+    ],
+    am_pm: ["", ""],
+    d_t_fmt: "%a %d %b %Y %T %Z",
+    d_fmt: "%d/%m/%Y",
+    t_fmt: "%T",
+    t_fmt_ampm: "",
+    alt_digits: None,
+    eras: &[],
+    era_d_t_fmt: "%a %d %b %Y %T %Z",
+    era_d_fmt: "%d/%m/%Y",
+    era_t_fmt: "%T",
+};
+
+/// Japanese (Japan).
+pub const JA_JP: StaticLocale = StaticLocale {
+    name: "ja_JP",
+    weekday_short: ["月", "火", "水", "木", "金", "土", "日"],
+    weekday_long: [
+        "月曜日",
+        "火曜日",
+        "水曜日",
+        "木曜日",
+        "金曜日",
+        "土曜日",
+        "日曜日",
+    ],
+    month_short: [
+        "1月", "2月", "3月", "4月", "5月", "6月", "7月", "8月", "9月", "10月", "11月", "12月",
+    ],
+    month_long: [
+        "1月", "2月", "3月", "4月", "5月", "6月", "7月", "8月", "9月", "10月", "11月", "12月",
+    ],
+    am_pm: ["午前", "午後"],
+    d_t_fmt: "%Y年%m月%d日 %H時%M分%S秒",
+    d_fmt: "%Y年%m月%d日",
+    t_fmt: "%H時%M分%S秒",
+    t_fmt_ampm: "%p%I時%M分%S秒",
+    alt_digits: Some(AltDigits::KANJI),
+    eras: &JAPANESE_ERAS,
+    era_d_t_fmt: "%EY%m月%d日 %H時%M分%S秒",
+    era_d_fmt: "%EY%m月%d日",
+    era_t_fmt: "%H時%M分%S秒",
+};
+
+/// Japanese (Japan), with eras abbreviated to the first letter of their romanized names, as in
+/// `R4.03.06`. Not included in `StaticLocale::ALL`.
+pub const JA_JP_ALPHABETIC_ERAS: StaticLocale = StaticLocale {
+    eras: &JAPANESE_ALPHABETIC_ERAS,
+    era_d_t_fmt: "%EY.%m.%d %H時%M分%S秒",
+    era_d_fmt: "%EY.%m.%d",
+    ..JA_JP
+};
+
+/// Korean (South Korea).
+pub const KO_KR: StaticLocale = StaticLocale {
+    name: "ko_KR",
+    weekday_short: ["월", "화", "수", "목", "금", "토", "일"],
+    weekday_long: [
+        "월요일",
+        "화요일",
+        "수요일",
+        "목요일",
+        "금요일",
+        "토요일",
+        "일요일",
+    ],
+    month_short: [
+        "1월", "2월", "3월", "4월", "5월", "6월", "7월", "8월", "9월", "10월", "11월", "12월",
+    ],
+    month_long: [
+        "1월", "2월", "3월", "4월", "5월", "6월", "7월", "8월", "9월", "10월", "11월", "12월",
+    ],
+    am_pm: ["오전", "오후"],
+    d_t_fmt: "%x (%a) %r",
+    d_fmt: "%Y년 %m월 %d일",
+    t_fmt: "%H시 %M분 %S초",
+    t_fmt_ampm: "%p %I시 %M분 %S초",
+    alt_digits: None,
+    eras: &[],
+    era_d_t_fmt: "%x (%a) %r",
+    era_d_fmt: "%Y년 %m월 %d일",
+    era_t_fmt: "%H시 %M분 %S초",
+};
+
+/// Portuguese (Brazil).
+pub const PT_BR: StaticLocale = StaticLocale {
+    name: "pt_BR",
+    weekday_short: ["seg", "ter", "qua", "qui", "sex", "sáb", "dom"],
+    weekday_long: [
+        "segunda", "terça", "quarta", "quinta", "sexta", "sábado", "domingo",
+    ],
+    month_short: [
+        "jan", "fev", "mar", "abr", "mai", "jun", "jul", "ago", "set", "out", "nov", "dez",
+    ],
+    month_long: [
+        "janeiro",
+        "fevereiro",
+        "março",
+        "abril",
+        "maio",
+        "junho",
+        "julho",
+        "agosto",
+        "setembro",
+        "outubro",
+        "novembro",
+        "dezembro",
+    ],
+    am_pm: ["", ""],
+    d_t_fmt: "%a %d %b %Y %T %Z",
+    d_fmt: "%d/%m/%Y",
+    t_fmt: "%T",
+    t_fmt_ampm: "",
+    alt_digits: None,
+    eras: &[],
+    era_d_t_fmt: "%a %d %b %Y %T %Z",
+    era_d_fmt: "%d/%m/%Y",
+    era_t_fmt: "%T",
+};
+
+/// Chinese (China).
+pub const ZH_CN: StaticLocale = StaticLocale {
+    name: "zh_CN",
+    weekday_short: ["一", "二", "三", "四", "五", "六", "日"],
+    weekday_long: [
+        "星期一",
+        "星期二",
+        "星期三",
+        "星期四",
+        "星期五",
+        "星期六",
+        "星期日",
+    ],
+    month_short: [
+        "1月", "2月", "3月", "4月", "5月", "6月", "7月", "8月", "9月", "10月", "11月", "12月",
+    ],
+    month_long: [
+        "一月",
+        "二月",
+        "三月",
+        "四月",
+        "五月",
+        "六月",
+        "七月",
+        "八月",
+        "九月",
+        "十月",
+        "十一月",
+        "十二月",
+    ],
+    am_pm: ["上午", "下午"],
+    d_t_fmt: "%Y年%m月%d日 %A %H时%M分%S秒",
+    d_fmt: "%Y年%m月%d日",
+    t_fmt: "%H时%M分%S秒",
+    t_fmt_ampm: "%p %I时%M分%S秒",
+    alt_digits: None,
+    eras: &[],
+    era_d_t_fmt: "%Y年%m月%d日 %A %H时%M分%S秒",
+    era_d_fmt: "%Y年%m月%d日",
+    era_t_fmt: "%H时%M分%S秒",
+};
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::{
+        StaticLocale, DE_DE, ES_ES, FR_FR, JA_JP, JA_JP_ALPHABETIC_ERAS, KO_KR, PT_BR, ZH_CN,
+    };
+    use crate::{
+        format::{
+            format_date_time_with_locale, format_zoned_date_time_with_locale, FormatError,
+            FormatOptions,
+        },
+        parse::{parse_strict_date_time_maybe_with_zone_with_locale, ParseError, ParseOptions},
+    };
+
+    #[test]
+    fn test_from_name() {
+        assert_eq!(StaticLocale::from_name("ja_JP"), Some(&JA_JP));
+        assert_eq!(StaticLocale::from_name("de_DE.UTF-8"), Some(&DE_DE));
+        assert_eq!(StaticLocale::from_name("en_US"), None);
+        for locale in StaticLocale::ALL {
+            assert_eq!(StaticLocale::from_name(locale.name()), Some(locale));
+        }
+    }
+
+    #[test]
+    fn test_format() -> Result<(), FormatError> {
+        let dt = datetime!(2022-03-06 12:34:56);
+        let offset = time::macros::offset!(+9:00);
+        for (locale, expected) in [
+            (
+                &DE_DE,
+                "So 06 Mär 2022 12:34:56 JST|06.03.2022|Sonntag März",
+            ),
+            (
+                &ES_ES,
+                "dom 06 mar 2022 12:34:56 JST|06/03/22|domingo marzo",
+            ),
+            (
+                &FR_FR,
+                "dim. 06 mars 2022 12:34:56 JST|06/03/2022|dimanche mars",
+            ),
+            (
+                &JA_JP,
+                "2022年03月06日 12時34分56秒|2022年03月06日|日曜日 3月",
+            ),
+            (
+                &KO_KR,
+                "2022년 03월 06일 (일) 오후 12시 34분 56초|2022년 03월 06일|일요일 3월",
+            ),
+            (
+                &PT_BR,
+                "dom 06 mar 2022 12:34:56 JST|06/03/2022|domingo março",
+            ),
+            (
+                &ZH_CN,
+                "2022年03月06日 星期日 12时34分56秒|2022年03月06日|星期日 三月",
+            ),
+        ] {
+            assert_eq!(
+                format_zoned_date_time_with_locale(
+                    "%c|%x|%A %B",
+                    dt,
+                    offset,
+                    "JST",
+                    locale,
+                    &FormatOptions::default()
+                )?,
+                expected
+            );
+        }
+        assert_eq!(
+            format_date_time_with_locale("%r", dt, &JA_JP, &FormatOptions::default())?,
+            "午後12時34分56秒"
+        );
+        assert_eq!(
+            format_date_time_with_locale("%r", dt, &DE_DE, &FormatOptions::default())?,
+            ""
+        );
+        assert_eq!(
+            format_date_time_with_locale("%Om月%Od日", dt, &JA_JP, &FormatOptions::default())?,
+            "三月六日"
+        );
+        assert_eq!(
+            format_date_time_with_locale("%Od", dt, &ZH_CN, &FormatOptions::default())?,
+            "06"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse() -> Result<(), ParseError> {
+        let dt = datetime!(2022-11-06 00:34:56);
+        for locale in StaticLocale::ALL {
+            let s = format_date_time_with_locale(
+                "%A %a %B %b %x %X",
+                dt,
+                locale,
+                &FormatOptions::default(),
+            )
+            .unwrap();
+            assert_eq!(
+                parse_strict_date_time_maybe_with_zone_with_locale(
+                    "%A %a %B %b %x %X",
+                    &s,
+                    locale,
+                    &ParseOptions::default()
+                )?,
+                (dt, None),
+                "{}",
+                locale.name()
+            );
+        }
+        assert_eq!(
+            parse_strict_date_time_maybe_with_zone_with_locale(
+                "%c",
+                "2022년 11월 06일 (일) 오전 12시 34분 56초",
+                &KO_KR,
+                &ParseOptions::default()
+            )?,
+            (dt, None)
+        );
+        assert_eq!(
+            parse_strict_date_time_maybe_with_zone_with_locale(
+                "%B %d %Y",
+                "十一月 6 2022",
+                &ZH_CN,
+                &ParseOptions::default()
+            )?,
+            (datetime!(2022-11-06 00:00), None)
+        );
+        for (locale, s) in [
+            (&ES_ES, "MARTES 1 MARZO 2022"),
+            (&ES_ES, "mar 1 mar 2022"),
+            (&FR_FR, "mardi 1 mars 2022"),
+            (&FR_FR, "MAR 1 MARS 2022"),
+            (&ES_ES, "mar. 1 mar. 2022"),
+            (&DE_DE, "dienstag 1 MÄRZ 2022"),
+            (&DE_DE, "Di. 1 Mär. 2022"),
+            (&PT_BR, "ter. 1 mar. 2022"),
+            (&PT_BR, "TERÇA 1 março 2022"),
+        ] {
+            assert_eq!(
+                parse_strict_date_time_maybe_with_zone_with_locale(
+                    "%a %d %b %Y",
+                    s,
+                    locale,
+                    &ParseOptions::default()
+                )?,
+                (datetime!(2022-03-01 00:00), None),
+                "{}",
+                s
+            );
+        }
+        assert_eq!(
+            parse_strict_date_time_maybe_with_zone_with_locale(
+                "%b %Y",
+                "Déc 2022",
+                &FR_FR,
+                &ParseOptions::default()
+            )?,
+            (datetime!(2022-12-01 00:00), None)
+        );
+        assert_eq!(
+            parse_strict_date_time_maybe_with_zone_with_locale(
+                "%p%I時",
+                "午後1時",
+                &JA_JP,
+                &ParseOptions::default()
+            )?,
+            (datetime!(1900-01-01 13:00), None)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_japanese_era() -> Result<(), Box<dyn std::error::Error>> {
+        for (dt, expected, alphabetic) in [
+            (datetime!(2022-03-06 00:00), "令和4年3月6日", "R4.03.06"),
+            (datetime!(2020-01-01 00:00), "令和2年1月1日", "R2.01.01"),
+            (datetime!(2019-12-31 00:00), "令和元年12月31日", "R1.12.31"),
+            (datetime!(2019-05-01 00:00), "令和元年5月1日", "R1.05.01"),
+            (datetime!(2019-04-30 00:00), "平成31年4月30日", "H31.04.30"),
+            (datetime!(1989-01-08 00:00), "平成元年1月8日", "H1.01.08"),
+            (datetime!(1989-01-07 00:00), "昭和64年1月7日", "S64.01.07"),
+            (datetime!(1926-12-25 00:00), "昭和元年12月25日", "S1.12.25"),
+            (datetime!(1926-12-24 00:00), "大正15年12月24日", "T15.12.24"),
+            (datetime!(1912-07-30 00:00), "大正元年7月30日", "T1.07.30"),
+            (datetime!(1912-07-29 00:00), "明治45年7月29日", "M45.07.29"),
+            (datetime!(1868-10-23 00:00), "明治元年10月23日", "M1.10.23"),
+        ] {
+            assert_eq!(
+                format_date_time_with_locale(
+                    "%EY%-m月%-d日",
+                    dt,
+                    &JA_JP,
+                    &FormatOptions::default()
+                )?,
+                expected
+            );
+            assert_eq!(
+                format_date_time_with_locale(
+                    "%Ex",
+                    dt,
+                    &JA_JP_ALPHABETIC_ERAS,
+                    &FormatOptions::default()
+                )?,
+                alphabetic
+            );
+            assert_eq!(
+                parse_strict_date_time_maybe_with_zone_with_locale(
+                    "%EY%m月%d日",
+                    expected,
+                    &JA_JP,
+                    &ParseOptions::default()
+                )?,
+                (dt, None)
+            );
+            assert_eq!(
+                parse_strict_date_time_maybe_with_zone_with_locale(
+                    "%Ex",
+                    alphabetic,
+                    &JA_JP_ALPHABETIC_ERAS,
+                    &ParseOptions::default()
+                )?,
+                (dt, None)
+            );
+        }
+
+        let dt = datetime!(2022-03-06 12:34:56);
+        assert_eq!(
+            format_date_time_with_locale(
+                "%Ec|%EC|%Ey|%_3Ey|%Ex|%EX",
+                dt,
+                &JA_JP,
+                &FormatOptions::default()
+            )?,
+            "令和4年03月06日 12時34分56秒|令和|4|  4|令和4年03月06日|12時34分56秒"
+        );
+        // Before the first era.
+        assert_eq!(
+            format_date_time_with_locale(
+                "%EC|%Ey|%EY",
+                datetime!(1868-10-22 00:00),
+                &JA_JP,
+                &FormatOptions::default()
+            )?,
+            "18|68|1868"
+        );
+        assert_eq!(
+            parse_strict_date_time_maybe_with_zone_with_locale(
+                "%Ey年 %EC",
+                "4年 令和",
+                &JA_JP,
+                &ParseOptions::default()
+            )?,
+            (datetime!(2022-01-01 00:00), None)
+        );
+        assert_eq!(
+            parse_strict_date_time_maybe_with_zone_with_locale(
+                "%EC",
+                "令和",
+                &JA_JP,
+                &ParseOptions::default()
+            )?,
+            (datetime!(2019-01-01 00:00), None)
+        );
+        // Works as `%y` without an era.
+        assert_eq!(
+            parse_strict_date_time_maybe_with_zone_with_locale(
+                "%Ey",
+                "22",
+                &JA_JP,
+                &ParseOptions::default()
+            )?,
+            (datetime!(2022-01-01 00:00), None)
+        );
+        assert_eq!(
+            parse_strict_date_time_maybe_with_zone_with_locale(
+                "%EY",
+                "天保4年",
+                &JA_JP,
+                &ParseOptions::default()
+            ),
+            Err(ParseError::UnexpectedByte("digits or sign", 0xe5))
+        );
+        assert_eq!(
+            parse_strict_date_time_maybe_with_zone_with_locale(
+                "%Y %EY",
+                "1999 昭和4年",
+                &JA_JP,
+                &ParseOptions::default()
+            )?,
+            (datetime!(1929-01-01 00:00), None)
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,128 @@
+/// Alternative symbols for numbers, used by `%O` modifiers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AltDigits<'a> {
+    /// Symbols replacing each of the decimal digits from 0 to 9, like fullwidth `０` to `９`.
+    Positional([char; 10]),
+    /// Symbols for each number from 0, like glibc's `ALT_DIGITS`. Numbers beyond them are written
+    /// with the ASCII digits.
+    Table(&'a [&'a str]),
+}
+
+impl AltDigits<'static> {
+    /// Fullwidth digits, `０` to `９`.
+    pub const FULLWIDTH: Self =
+        Self::Positional(['０', '１', '２', '３', '４', '５', '６', '７', '８', '９']);
+    /// Arabic-Indic digits, `٠` to `٩`.
+    pub const ARABIC_INDIC: Self =
+        Self::Positional(['٠', '١', '٢', '٣', '٤', '٥', '٦', '٧', '٨', '٩']);
+    /// Devanagari digits, `०` to `९`.
+    pub const DEVANAGARI: Self =
+        Self::Positional(['०', '१', '२', '३', '४', '५', '६', '७', '८', '९']);
+    /// Thai digits, `๐` to `๙`.
+    pub const THAI: Self = Self::Positional(['๐', '๑', '๒', '๓', '๔', '๕', '๖', '๗', '๘', '๙']);
+    /// Kanji numerals from `〇` to `九十九`, as in `ja_JP` of glibc.
+    pub const KANJI: Self = Self::Table(&KANJI_NUMERALS);
+}
+
+const KANJI_NUMERALS: [&str; 100] = [
+    "〇",
+    "一",
+    "二",
+    "三",
+    "四",
+    "五",
+    "六",
+    "七",
+    "八",
+    "九",
+    "十",
+    "十一",
+    "十二",
+    "十三",
+    "十四",
+    "十五",
+    "十六",
+    "十七",
+    "十八",
+    "十九",
+    "二十",
+    "二十一",
+    "二十二",
+    "二十三",
+    "二十四",
+    "二十五",
+    "二十六",
+    "二十七",
+    "二十八",
+    "二十九",
+    "三十",
+    "三十一",
+    "三十二",
+    "三十三",
+    "三十四",
+    "三十五",
+    "三十六",
+    "三十七",
+    "三十八",
+    "三十九",
+    "四十",
+    "四十一",
+    "四十二",
+    "四十三",
+    "四十四",
+    "四十五",
+    "四十六",
+    "四十七",
+    "四十八",
+    "四十九",
+    "五十",
+    "五十一",
+    "五十二",
+    "五十三",
+    "五十四",
+    "五十五",
+    "五十六",
+    "五十七",
+    "五十八",
+    "五十九",
+    "六十",
+    "六十一",
+    "六十二",
+    "六十三",
+    "六十四",
+    "六十五",
+    "六十六",
+    "六十七",
+    "六十八",
+    "六十九",
+    "七十",
+    "七十一",
+    "七十二",
+    "七十三",
+    "七十四",
+    "七十五",
+    "七十六",
+    "七十七",
+    "七十八",
+    "七十九",
+    "八十",
+    "八十一",
+    "八十二",
+    "八十三",
+    "八十四",
+    "八十五",
+    "八十六",
+    "八十七",
+    "八十八",
+    "八十九",
+    "九十",
+    "九十一",
+    "九十二",
+    "九十三",
+    "九十四",
+    "九十五",
+    "九十六",
+    "九十七",
+    "九十八",
+    "九十九",
+];
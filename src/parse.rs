@@ -1,16 +1,35 @@
 use thiserror::Error;
-use time::{Date, Month, PrimitiveDateTime, Time, UtcOffset, Weekday};
+use time::{Date, Duration, Month, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset, Weekday};
 
-use crate::{parse::desc_parser::Collector, util};
+use crate::{
+    calendar::Calendar,
+    locale::{AltDigits, Era, Expanding, Locale, LocaleFormat, PosixLocale},
+    parse::desc_parser::{Collector, Spec},
+    util::{self, Modifier},
+};
 
+mod compiled;
 mod desc_parser;
+mod parsed;
+// Components of `FormatItem` we rely on are deprecated in newer versions of the time crate, but we
+// keep using them to support the older ones.
+#[allow(deprecated)]
 pub mod time_format_item;
 
+pub use compiled::StrptimeFormat;
+pub use parsed::Parsed;
+
 #[derive(Error, Debug, PartialEq, Eq)]
 #[non_exhaustive]
 pub enum ParseError {
     #[error("Unknown specifier `%{0}`")]
     UnknownSpecifier(char),
+    #[error("Locale-provided format expands into itself")]
+    RecursiveLocaleFormat,
+    #[error("Field width {1} is not supported by `%{0}`")]
+    UnsupportedWidth(char, usize),
+    #[error("Precision {1} is not supported by `%{0}`")]
+    UnsupportedPrecision(char, usize),
     #[error("Expected {0} but got a byte {1}")]
     UnexpectedByte(&'static str, u8),
     #[error("Expected {0} but reached to the end")]
@@ -19,10 +38,99 @@ pub enum ParseError {
     NotMatch(&'static str),
     #[error("Out-of-range for {0} component")]
     ComponentOutOfRange(&'static str),
+    #[error("Digits beyond the precision of {0} were truncated")]
+    TruncatedDigits(&'static str),
     #[error("Unconverted data remains: {0}")]
     UnconvertedDataRemains(String),
     #[error(transparent)]
     ComponentRange(#[from] time::error::ComponentRange),
+    #[error("Inconsistent with the result: {}", .0.join(", "))]
+    Inconsistent(Vec<&'static str>),
+    #[error("No offset was parsed and no default offset was given")]
+    MissingOffset,
+    #[error("Unknown timezone name: {0}")]
+    UnknownTimeZone(String),
+    #[error("The result can't hold the {0} component")]
+    UnexpectedComponent(&'static str),
+    #[error("The {0} component is required but not given")]
+    MissingComponent(&'static str),
+}
+
+/// How `%y` and `%g` without `%C` determine the century, by the 100 years the year is in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum YearPivot {
+    /// The years from the given one, e.g. `Fixed(1969)`, the default, makes `69` to `99` in the
+    /// 1900s and `00` to `68` in the 2000s as POSIX does.
+    Fixed(i32),
+    /// The years from `before` years before `reference`, e.g. `before: 80` makes it from 80 years
+    /// before to 19 years after `reference`, which is usually the current year.
+    Sliding { reference: i32, before: u8 },
+}
+
+impl YearPivot {
+    /// The first 2 digits of the year ending with `suffix`.
+    fn century(self, suffix: u8) -> i32 {
+        let start = match self {
+            Self::Fixed(start) => i64::from(start),
+            Self::Sliding { reference, before } => i64::from(reference) - i64::from(before),
+        };
+        let year = start + (i64::from(suffix) - start).rem_euclid(100);
+        year.div_euclid(100) as i32
+    }
+}
+
+impl Default for YearPivot {
+    fn default() -> Self {
+        Self::Fixed(1969)
+    }
+}
+
+/// Options that change how the input is parsed, taken by the `parse_*_with_locale` functions and
+/// [`StrptimeFormat::with_options`]. The default is what the other `parse_*` functions of this
+/// module do.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    check_consistency: bool,
+    reference: Option<OffsetDateTime>,
+    closest_year: bool,
+    year_pivot: YearPivot,
+}
+
+impl ParseOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes parsing fail with [`ParseError::Inconsistent`] listing the fields that disagree with
+    /// the result, like a weekday of `%a` with the date, or `%j` with `%m` and `%d`, instead of
+    /// silently preferring one of them.
+    pub fn check_consistency(mut self, check: bool) -> Self {
+        self.check_consistency = check;
+        self
+    }
+
+    /// Fills the fields not given and higher-order than any given from `reference`, instead of
+    /// from 1900-01-01 00:00:00. A time alone is on the day of `reference`, `%b %e` is in the year
+    /// of it, and `%M:%S` in the hour of it, all taken at the offset parsed if any.
+    pub fn reference(mut self, reference: OffsetDateTime) -> Self {
+        self.reference = Some(reference);
+        self
+    }
+
+    /// Makes the year filled from the reference the one the result is the closest to it in, e.g.
+    /// so that a date in December parsed in January is in the previous year, and a leap day is in
+    /// a leap year.
+    pub fn closest_year(mut self, closest: bool) -> Self {
+        self.closest_year = closest;
+        self
+    }
+
+    /// Replaces how `%y` and `%g` determine the century unless `%C` is given.
+    pub fn year_pivot(mut self, pivot: YearPivot) -> Self {
+        self.year_pivot = pivot;
+        self
+    }
 }
 
 trait Nat: std::ops::Add<Output = Self> + std::ops::Mul<Output = Self>
@@ -73,54 +181,290 @@ impl Nat for i32 {
     }
 }
 impl Int for i32 {}
+impl Nat for i128 {
+    const ZERO: Self = 0;
+    const TEN: Self = 10;
+    fn from_u8(v: u8) -> Self {
+        v as i128
+    }
+}
+impl Int for i128 {}
+
+/// The maximum number of digits `%f` takes.
+#[inline]
+fn subsecond_digits(spec: Spec) -> Result<usize, ParseError> {
+    match spec.width {
+        None => Ok(9),
+        Some(width @ 1..=9) => Ok(width),
+        Some(width) => Err(ParseError::UnsupportedWidth('f', width)),
+    }
+}
 
-#[derive(Debug)]
+/// The number of fractional digits `%s` should have.
+#[inline]
+fn timestamp_precision(spec: Spec) -> Result<u32, ParseError> {
+    match spec.precision {
+        None => Ok(0),
+        Some(precision @ 0..=9) => Ok(precision as u32),
+        Some(precision) => Err(ParseError::UnsupportedPrecision('s', precision)),
+    }
+}
+
+/// The nanoseconds since the Unix epoch of the local date time `timestamp` plus `nanosecond` at
+/// `offset`.
+#[inline]
+fn timestamp_nanos(
+    timestamp: i128,
+    nanosecond: u32,
+    offset: UtcOffset,
+) -> Result<i128, ParseError> {
+    timestamp
+        .checked_add(i128::from(nanosecond))
+        .and_then(|nanos| nanos.checked_add(i128::from(offset.whole_seconds()) * 1_000_000_000))
+        .ok_or(ParseError::ComponentOutOfRange("seconds-since-epoch"))
+}
+
+#[derive(Clone, Copy, Debug)]
 enum ParsingYear {
     Unspecified,
     Year(i32),
     PrefixSuffix(i32, u8),
 }
-#[derive(Debug)]
+impl ParsingYear {
+    /// The year, if specified.
+    fn get(self) -> Result<Option<i32>, ParseError> {
+        match self {
+            Self::Unspecified => Ok(None),
+            Self::Year(y) => Ok(Some(y)),
+            Self::PrefixSuffix(p, s) => p
+                .checked_mul(100)
+                .and_then(|p| p.checked_add(s as i32))
+                .map(Some)
+                .ok_or(ParseError::ComponentOutOfRange("year")),
+        }
+    }
+}
+#[derive(Clone, Copy, Debug)]
 enum ParsingDayOfYear {
     Unspecified,
     MonthDay(Month, u8),
     DayOfYear(u16),
 }
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug)]
 enum ParsingHour {
     Unspecified,
     FullDay(u8),
     HalfDay(u8, bool),
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum TimeZoneSpecifier<'a> {
     Offset(UtcOffset),
     Name(&'a str),
 }
 
-struct ParseCollector<'a> {
+/// The date of `weekday` in the `week`-th week of `year`, where the weeks start on `first` and the
+/// first one is the one with the first `first` of the year, as glibc computes from `%U` or `%W`.
+///
+/// Weeks are always of the Gregorian year, as the formatter counts them, whatever the calendar of
+/// the locale is.
+fn week_date(
+    year: i32,
+    week: u8,
+    first: Weekday,
+    weekday: Weekday,
+) -> Result<Option<Date>, ParseError> {
+    let new_year = Date::from_ordinal_date(year, 1)?;
+    let days_from_monday = |weekday: Weekday| i64::from(weekday.number_days_from_monday());
+    let first_week = (days_from_monday(first) - days_from_monday(new_year.weekday())).rem_euclid(7);
+    let days = first_week
+        + (i64::from(week) - 1) * 7
+        + (days_from_monday(weekday) - days_from_monday(first)).rem_euclid(7);
+    Ok(new_year.checked_add(Duration::days(days)))
+}
+
+/// The date `to_date` gives for a Gregorian year, of which the week numbers are, that is in `year`
+/// of `calendar`. A year of another calendar overlaps two Gregorian years, and the first of them
+/// that works is taken.
+fn in_gregorian_year(
+    calendar: &dyn Calendar,
+    year: i32,
+    to_date: impl Fn(i32) -> Result<Option<Date>, ParseError>,
+) -> Result<Option<Date>, ParseError> {
+    let first = calendar.ordinal_to_date(year, 1);
+    if first.is_some() && first == Date::from_ordinal_date(year, 1).ok() {
+        // The year is the Gregorian one.
+        return to_date(year);
+    }
+    let Some(first) = first else {
+        return Ok(None);
+    };
+    Ok([first.year(), first.year() + 1]
+        .into_iter()
+        .filter_map(|gregorian| to_date(gregorian).ok().flatten())
+        .find(|&date| calendar.calendar_date(date).0 == year))
+}
+
+/// Fields as given, which may be overridden by others, to check them against the result.
+#[derive(Clone, Copy, Debug, Default)]
+struct GivenFields {
+    /// `%Y`.
+    year: Option<i32>,
+    /// `%C`.
+    year_prefix: Option<i32>,
+    /// `%y`.
+    year_suffix: Option<u8>,
+    /// `%m` or `%b`.
+    month: Option<Month>,
+    /// `%d`.
+    day: Option<u8>,
+    /// `%j`.
+    day_of_year: Option<u16>,
+    /// `%U`.
+    sunday_week: Option<u8>,
+    /// `%W`.
+    monday_week: Option<u8>,
+    /// `%H`.
+    hour: Option<u8>,
+    /// `%I`.
+    hour_12: Option<u8>,
+    /// `%p`.
+    pm: Option<bool>,
+    /// `%M`.
+    minute: Option<u8>,
+    /// `%S`.
+    second: Option<u8>,
+    /// `%f`.
+    nanosecond: Option<u32>,
+    /// The number of digits of `%f` that `nanosecond` holds.
+    subsecond_digits: Option<u8>,
+}
+
+/// What a component given is a part of, to check that the result can hold it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Part {
+    Date,
+    Time,
+    Offset,
+    /// The timestamp and the timezone name, which none of `Date`, `Time` and `UtcOffset` can hold.
+    Other,
+}
+
+/// The date the fields not given default to, as glibc does.
+const DEFAULT_DATE: Date = util::date(1900, Month::January, 1);
+
+#[derive(Clone)]
+struct ParseCollector<'a, 'l> {
     s: &'a str,
     year: ParsingYear,
+    /// The era given by `%EC` or `%EY`, which determines the year along with `year_of_era`.
+    era: Option<&'l Era<'l>>,
+    /// `%Ey`.
+    year_of_era: Option<i32>,
     day: ParsingDayOfYear,
+    /// `%G` or `%g`.
+    iso_year: ParsingYear,
+    /// `%V`.
+    iso_week: Option<u8>,
+    /// `%a`, `%u` or `%w`.
+    weekday: Option<Weekday>,
+    /// `%U` or `%W`, with the first day of the weeks.
+    week_number: Option<(u8, Weekday)>,
     hour: ParsingHour,
     minute: u8,
     second: u8,
     nanosecond: u32,
+    /// Whether `%f` dropped some digits.
+    truncated_subsecond: bool,
+    /// Nanoseconds since the Unix epoch.
+    timestamp: Option<i128>,
     zone: Option<TimeZoneSpecifier<'a>>,
+    given: GivenFields,
+    options: ParseOptions,
+    locale: &'l dyn Locale,
+    /// Locale-provided formats being expanded.
+    expanding: Expanding,
 }
-impl<'a> ParseCollector<'a> {
+impl<'a, 'l> ParseCollector<'a, 'l> {
     fn new(s: &'a str) -> Self {
         Self {
             s,
             year: ParsingYear::Unspecified,
+            era: None,
+            year_of_era: None,
             day: ParsingDayOfYear::Unspecified,
+            iso_year: ParsingYear::Unspecified,
+            iso_week: None,
+            weekday: None,
+            week_number: None,
             hour: ParsingHour::Unspecified,
             minute: 0,
             second: 0,
             nanosecond: 0,
+            truncated_subsecond: false,
+            timestamp: None,
             zone: None,
+            given: GivenFields::default(),
+            options: ParseOptions::default(),
+            locale: &PosixLocale,
+            expanding: Expanding::default(),
+        }
+    }
+
+    #[inline]
+    fn with_locale(self, locale: &'l dyn Locale) -> Self {
+        Self { locale, ..self }
+    }
+
+    #[inline]
+    fn with_options(self, options: ParseOptions) -> Self {
+        Self { options, ..self }
+    }
+
+    /// Parses with the locale-provided `format`.
+    fn expand(&mut self, format: LocaleFormat) -> Result<(), ParseError> {
+        let expanding = self
+            .expanding
+            .enter(format)
+            .ok_or(ParseError::RecursiveLocaleFormat)?;
+        let saved = self.expanding;
+        self.expanding = expanding;
+        let result = desc_parser::collect(format.get(self.locale), self);
+        self.expanding = saved;
+        result
+    }
+
+    /// Whether `%E` is given and the locale has eras.
+    #[inline]
+    fn has_era(&self, spec: Spec) -> bool {
+        spec.modifier == Some(Modifier::Era) && !self.locale.eras().is_empty()
+    }
+
+    /// Parses the year with the format of one of the eras, trying them in order. Returns whether
+    /// any of them matched.
+    fn era_year(&mut self) -> Result<bool, ParseError> {
+        let expanding = self
+            .expanding
+            .enter_era_year()
+            .ok_or(ParseError::RecursiveLocaleFormat)?;
+        for era in self.locale.eras() {
+            let mut attempt = self.clone();
+            attempt.expanding = expanding;
+            attempt.era = Some(era);
+            attempt.year_of_era = None;
+            match desc_parser::collect(era.format, &mut attempt) {
+                Ok(()) => {
+                    attempt.expanding = self.expanding;
+                    *self = attempt;
+                    return Ok(true);
+                }
+                Err(ParseError::RecursiveLocaleFormat) => {
+                    return Err(ParseError::RecursiveLocaleFormat)
+                }
+                Err(_) => {}
+            }
         }
+        Ok(false)
     }
 
     #[inline]
@@ -141,47 +485,117 @@ impl<'a> ParseCollector<'a> {
         self.s.bytes().next()
     }
 
+    /// The alternative digits, if `%O` is given and the locale has them.
+    #[inline]
+    fn alt_digits(&self, spec: Spec) -> Option<AltDigits<'l>> {
+        if spec.modifier == Some(Modifier::AlternativeDigits) {
+            self.locale.alt_digits()
+        } else {
+            None
+        }
+    }
+
+    /// Consumes a number from the table of alternative digits, if `%O` is given and the locale
+    /// has one.
+    #[inline]
+    fn consume_alt_number<N: Nat>(&mut self, spec: Spec) -> Option<N> {
+        match self.alt_digits(spec) {
+            Some(AltDigits::Table(table)) => {
+                let s = self.s;
+                let matches = table
+                    .iter()
+                    .zip(0..=u8::MAX)
+                    .filter(|(digits, _)| !digits.is_empty())
+                    .map(|(digits, v)| (util::strip_prefix_ignore_case(s, digits), v));
+                self.consume_longest(matches).map(N::from_u8)
+            }
+            _ => None,
+        }
+    }
+
+    /// The value and the length of the digit at the start of `s`, which is an ASCII one or one of
+    /// `alt_digits`.
+    #[inline]
+    fn leading_digit(s: &str, alt_digits: Option<[char; 10]>) -> Option<(u8, usize)> {
+        let c = s.chars().next()?;
+        if c.is_ascii_digit() {
+            return Some((c as u8 - b'0', 1));
+        }
+        let v = alt_digits?.iter().position(|&d| d == c)?;
+        Some((v as u8, c.len_utf8()))
+    }
+
+    /// Parses `min_len` to `max_len` digits. With `%O`, the alternative digits of the locale are
+    /// accepted as well.
     /// Note: Need a change if pass max_len that makes us require checking for overflow.
     #[inline]
-    fn parse_nat<N: Nat>(&mut self, min_len: usize, max_len: usize) -> Result<N, ParseError> {
+    fn parse_nat<N: Nat>(
+        &mut self,
+        spec: Spec,
+        min_len: usize,
+        max_len: usize,
+    ) -> Result<N, ParseError> {
+        if let Some(v) = self.consume_alt_number(spec) {
+            return Ok(v);
+        }
+        let alt_digits = match self.alt_digits(spec) {
+            Some(AltDigits::Positional(alt_digits)) => Some(alt_digits),
+            _ => None,
+        };
         if self.s.len() < min_len {
             return Err(ParseError::UnexpectedEnd("digits"));
         }
-        let bytes = self.s.as_bytes();
-        let max_len = max_len.min(bytes.len());
         let mut res = N::ZERO;
-        let mut bytes_read = 0;
-        for &c in &bytes[..max_len] {
-            if (b'0'..=b'9').contains(&c) {
-                res = (res * N::TEN) + N::from_u8(c - b'0');
-            } else if bytes_read < min_len {
-                return Err(ParseError::UnexpectedByte("digits", c));
-            } else {
-                break;
+        let mut s = self.s;
+        for digits_read in 0..max_len {
+            match Self::leading_digit(s, alt_digits) {
+                Some((d, len)) => {
+                    res = (res * N::TEN) + N::from_u8(d);
+                    s = &s[len..];
+                }
+                None if digits_read < min_len => {
+                    return Err(match s.bytes().next() {
+                        Some(c) => ParseError::UnexpectedByte("digits", c),
+                        None => ParseError::UnexpectedEnd("digits"),
+                    })
+                }
+                None => break,
             }
-            bytes_read += 1;
         }
-        self.s = &self.s[bytes_read..];
+        self.s = s;
         Ok(res)
     }
 
-    /// Allows '+'/'-'.
+    /// Allows '+'/'-'. With `%O`, the alternative digits of the locale are accepted as well.
     /// Note: Need a change if pass max_len that makes us require checking for overflow.
     #[inline]
-    fn parse_int<Z: Int>(&mut self, max_len: usize) -> Result<Z, ParseError> {
+    fn parse_int<Z: Int>(&mut self, spec: Spec, max_len: usize) -> Result<Z, ParseError> {
+        if let Some(v) = self.consume_alt_number(spec) {
+            return Ok(v);
+        }
+        let alt_digits = match self.alt_digits(spec) {
+            Some(AltDigits::Positional(alt_digits)) => Some(alt_digits),
+            _ => None,
+        };
         if self.s.is_empty() {
             return Err(ParseError::UnexpectedEnd("digits"));
         }
-        let max_len = max_len.min(self.s.len());
         let mut res = Z::ZERO;
-        let mut bytes_read = 0;
+        let mut s = self.s;
         let mut negate = false;
         let mut had_digit = false;
-        for &c in &self.s.as_bytes()[..max_len] {
-            if (b'0'..=b'9').contains(&c) {
-                res = (res * Z::TEN) + Z::from_u8(c - b'0');
+        for chars_read in 0..max_len {
+            if let Some((d, len)) = Self::leading_digit(s, alt_digits) {
+                res = (res * Z::TEN) + Z::from_u8(d);
                 had_digit = true;
-            } else if bytes_read == 0 {
+                s = &s[len..];
+                continue;
+            }
+            let c = match s.bytes().next() {
+                Some(c) => c,
+                None => break,
+            };
+            if chars_read == 0 {
                 if c == b'+' {
                     // skip it
                 } else if c == b'-' {
@@ -189,26 +603,433 @@ impl<'a> ParseCollector<'a> {
                 } else {
                     return Err(ParseError::UnexpectedByte("digits or sign", c));
                 }
+                s = &s[1..];
             } else if had_digit {
                 break;
             } else {
                 return Err(ParseError::UnexpectedByte("digits", c));
             }
-            bytes_read += 1;
         }
-        self.s = &self.s[bytes_read..];
+        // A sign alone isn't a number.
+        if !had_digit {
+            return Err(match s.bytes().next() {
+                Some(c) => ParseError::UnexpectedByte("digits", c),
+                None => ParseError::UnexpectedEnd("digits"),
+            });
+        }
+        self.s = s;
         Ok(if negate { -res } else { res })
     }
 
+    /// Parses two digits of an offset component preceded by `:` if `colon`, if it seems to be
+    /// there.
     #[inline]
-    fn starts_with_ignore_ascii_case(&self, prefix: &str) -> bool {
-        self.s.len() >= prefix.len()
-            && self.s.is_char_boundary(prefix.len())
-            && self.s[..prefix.len()].eq_ignore_ascii_case(prefix)
+    fn parse_offset_component(&mut self, colon: bool) -> Result<Option<u8>, ParseError> {
+        if colon {
+            if self.peek_byte() != Some(b':') {
+                return Ok(None);
+            }
+            self.s = &self.s[1..]; // skip :
+        } else if !matches!(self.peek_byte(), Some(b'0'..=b'9')) {
+            return Ok(None);
+        }
+        self.parse_nat(Spec::default(), 2, 2).map(Some)
+    }
+
+    /// Consumes the longest of `names` the input starts with ignoring the case, and returns the
+    /// value associated to it. A name matches with or without a trailing period.
+    fn consume_longest_name<T>(
+        &mut self,
+        names: impl IntoIterator<Item = (&'l str, T)>,
+    ) -> Option<T> {
+        let s = self.s;
+        self.consume_longest(names.into_iter().map(|(name, value)| {
+            let name = name.strip_suffix('.').unwrap_or(name);
+            let rest = Some(name)
+                .filter(|name| !name.is_empty())
+                .and_then(|name| util::strip_prefix_ignore_case(s, name))
+                .map(|rest| rest.strip_prefix('.').unwrap_or(rest));
+            (rest, value)
+        }))
+    }
+
+    /// Consumes the longest of the matches, each given as the rest of the input if it matches,
+    /// and returns the value associated to it.
+    fn consume_longest<T>(
+        &mut self,
+        matches: impl IntoIterator<Item = (Option<&'a str>, T)>,
+    ) -> Option<T> {
+        let mut longest: Option<(&'a str, T)> = None;
+        for (rest, value) in matches {
+            if let Some(rest) = rest {
+                if longest.as_ref().is_none_or(|(r, _)| rest.len() < r.len()) {
+                    longest = Some((rest, value));
+                }
+            }
+        }
+        let (rest, value) = longest?;
+        self.s = rest;
+        Some(value)
+    }
+
+    /// The year, if the era or any of the year fields is given.
+    fn year(&self) -> Result<Option<i32>, ParseError> {
+        // `%Ey` alone is enough if the locale has only one era, e.g. the Buddhist one.
+        let era = self
+            .era
+            .or_else(|| self.year_of_era.and_then(|_| Era::sole(self.locale.eras())));
+        match era {
+            // Prefer the era over anything else, as glibc does.
+            Some(era) => {
+                let year_of_era = self.year_of_era.unwrap_or(era.offset);
+                Ok(Some(era.to_year(year_of_era, self.locale.calendar())))
+            }
+            None => self.year.get(),
+        }
+    }
+
+    /// The fields as given, with the year and the hour put together from their parts.
+    fn parsed(&self) -> Result<Parsed<'a>, ParseError>
+    where
+        'l: 'a,
+    {
+        let given = self.given;
+        let hour = match self.hour {
+            ParsingHour::FullDay(h) => Some(h),
+            // `%I` alone is ambiguous.
+            ParsingHour::HalfDay(h, pm) if given.hour_12.is_some() && given.pm.is_some() => {
+                Some(if pm { h + 12 } else { h })
+            }
+            _ => None,
+        };
+        let iso_year_of_century = match self.iso_year {
+            ParsingYear::PrefixSuffix(_, y) => Some(y),
+            _ => None,
+        };
+        let (sunday_week, monday_week) = match self.week_number {
+            Some((week, Weekday::Sunday)) => (Some(week), None),
+            Some((week, _)) => (None, Some(week)),
+            None => (None, None),
+        };
+        Ok(Parsed {
+            calendar: self.locale.calendar(),
+            year: self.year()?,
+            century: given.year_prefix,
+            year_of_century: given.year_suffix,
+            month: given.month,
+            day: given.day,
+            ordinal: given.day_of_year,
+            iso_year: self.iso_year.get()?,
+            iso_year_of_century,
+            iso_week: self.iso_week,
+            sunday_week,
+            monday_week,
+            weekday: self.weekday,
+            hour,
+            hour_12: given.hour_12,
+            pm: given.pm,
+            minute: given.minute,
+            second: given.second,
+            nanosecond: given.nanosecond,
+            subsecond_digits: given.subsecond_digits,
+            unix_timestamp_nanos: self.timestamp,
+            zone: self.zone,
+        })
+    }
+
+    /// The date time the fields determine.
+    fn resolve(&self) -> Result<(PrimitiveDateTime, Option<TimeZoneSpecifier<'a>>), ParseError> {
+        if let Some(timestamp) = self.timestamp {
+            // The timestamp determines everything. The local date time is taken at the parsed
+            // offset if any, or at UTC.
+            let offset = match self.zone {
+                Some(TimeZoneSpecifier::Offset(offset)) => offset,
+                _ => UtcOffset::UTC,
+            };
+            // `%f` after `%s` is the fractional part of the second.
+            let nanos = timestamp_nanos(timestamp, self.nanosecond, offset)?;
+            let date_time = OffsetDateTime::from_unix_timestamp_nanos(nanos)?;
+            return Ok((
+                PrimitiveDateTime::new(date_time.date(), date_time.time()),
+                Some(TimeZoneSpecifier::Offset(offset)),
+            ));
+        }
+        let calendar = self.locale.calendar();
+        // The reference at the parsed offset, as the input is local to it.
+        let reference = match (self.options.reference, self.zone) {
+            (Some(reference), Some(TimeZoneSpecifier::Offset(offset))) => {
+                Some(reference.to_offset(offset))
+            }
+            (reference, _) => reference,
+        };
+        let time = self.time(reference.map(OffsetDateTime::time))?;
+        let date = match (self.year()?, reference) {
+            (Some(year), _) => self.date(year, None)?,
+            // The year 1900 is in, in the calendar of the locale.
+            (None, None) => self.date(calendar.calendar_date(DEFAULT_DATE).0, None)?,
+            (None, Some(reference)) => {
+                let (year, month, day) = calendar.calendar_date(reference.date());
+                let fill = Some((month, day));
+                let reference = PrimitiveDateTime::new(reference.date(), reference.time());
+                let closest = self.options.closest_year.then(|| {
+                    // A leap day is at most 8 years apart from another, e.g. 1896-02-29 and
+                    // 1904-02-29.
+                    (year - 8..=year + 8)
+                        .filter_map(|year| self.date(year, fill).ok())
+                        .min_by_key(|&date| (PrimitiveDateTime::new(date, time) - reference).abs())
+                });
+                match closest.flatten() {
+                    Some(date) => date,
+                    None => self.date(year, fill)?,
+                }
+            }
+        };
+        let zone = self.zone;
+        Ok((PrimitiveDateTime::new(date, time), zone))
+    }
+
+    /// The date the fields determine in `year`. The month and the day are taken from `fill`, if
+    /// any, unless the day or the month is given respectively.
+    fn date(&self, year: i32, fill: Option<(Month, u8)>) -> Result<Date, ParseError> {
+        let calendar = self.locale.calendar();
+        let has_iso_week_date =
+            self.iso_week.is_some() || !matches!(self.iso_year, ParsingYear::Unspecified);
+        match (self.day, self.week_number, self.weekday) {
+            // The first week and Monday unless given, and the ISO 8601 week-based year is the
+            // year unless given.
+            (ParsingDayOfYear::Unspecified, _, weekday) if has_iso_week_date => {
+                let iso_week_date = |iso_year| {
+                    Ok(Some(Date::from_iso_week_date(
+                        iso_year,
+                        self.iso_week.unwrap_or(1),
+                        weekday.unwrap_or(Weekday::Monday),
+                    )?))
+                };
+                match self.iso_year.get()? {
+                    Some(iso_year) => iso_week_date(iso_year)?,
+                    None => in_gregorian_year(calendar, year, iso_week_date)?,
+                }
+            }
+            // Needs both the week number and the weekday, as glibc does.
+            (ParsingDayOfYear::Unspecified, Some((week, first)), Some(weekday)) => {
+                in_gregorian_year(calendar, year, |year| week_date(year, week, first, weekday))?
+            }
+            (ParsingDayOfYear::Unspecified, _, _) => match fill {
+                Some((month, day)) => calendar.to_date(year, month, day),
+                None => calendar.ordinal_to_date(year, 1),
+            },
+            (ParsingDayOfYear::MonthDay(month, day), _, _) => {
+                let month = match fill {
+                    Some((fill, _)) if self.given.month.is_none() => fill,
+                    _ => month,
+                };
+                calendar.to_date(year, month, day)
+            }
+            (ParsingDayOfYear::DayOfYear(day), _, _) => calendar.ordinal_to_date(year, day),
+        }
+        .ok_or(ParseError::ComponentOutOfRange("date"))
+    }
+
+    /// The time the fields determine, where the fields not given are 0, or from `reference` if
+    /// any and higher-order than one given.
+    fn time(&self, reference: Option<Time>) -> Result<Time, ParseError> {
+        let given = self.given;
+        let mut hour = match self.hour {
+            ParsingHour::Unspecified => 0,
+            ParsingHour::FullDay(h) => h,
+            ParsingHour::HalfDay(h, ampm) => {
+                if ampm {
+                    h + 12
+                } else {
+                    h
+                }
+            }
+        };
+        let (mut minute, mut second) = (self.minute, self.second);
+        if let Some(reference) = reference {
+            let has_hour = given.hour.is_some() || given.hour_12.is_some();
+            let has_minute = given.minute.is_some();
+            let has_second = given.second.is_some();
+            let has_nanosecond = given.nanosecond.is_some();
+            if !has_hour && (has_minute || has_second || has_nanosecond) {
+                hour = reference.hour();
+            }
+            if !has_minute && (has_second || has_nanosecond) {
+                minute = reference.minute();
+            }
+            if !has_second && has_nanosecond {
+                second = reference.second();
+            }
+        }
+        Ok(Time::from_hms_nano(hour, minute, second, self.nanosecond)?)
+    }
+
+    /// The components given, with what they are a part of.
+    fn components(&self) -> impl Iterator<Item = (&'static str, Part)> {
+        let given = self.given;
+        let year = given.year.is_some()
+            || given.year_suffix.is_some()
+            || self.era.is_some()
+            || self.year_of_era.is_some();
+        let (offset, timezone_name) = match self.zone {
+            Some(TimeZoneSpecifier::Offset(_)) => (true, false),
+            Some(TimeZoneSpecifier::Name(_)) => (false, true),
+            None => (false, false),
+        };
+        [
+            ("year", Part::Date, year),
+            ("year-prefix", Part::Date, given.year_prefix.is_some()),
+            ("month", Part::Date, given.month.is_some()),
+            ("day-of-month", Part::Date, given.day.is_some()),
+            ("day-of-year", Part::Date, given.day_of_year.is_some()),
+            ("day-of-week", Part::Date, self.weekday.is_some()),
+            ("week-number", Part::Date, self.week_number.is_some()),
+            ("iso-week-number", Part::Date, self.iso_week.is_some()),
+            (
+                "iso-week-based-year",
+                Part::Date,
+                !matches!(self.iso_year, ParsingYear::Unspecified),
+            ),
+            ("hour-of-day", Part::Time, given.hour.is_some()),
+            ("hour-of-half-day", Part::Time, given.hour_12.is_some()),
+            ("am/pm", Part::Time, given.pm.is_some()),
+            ("minute", Part::Time, given.minute.is_some()),
+            ("second", Part::Time, given.second.is_some()),
+            ("subsecond", Part::Time, given.nanosecond.is_some()),
+            ("offset", Part::Offset, offset),
+            ("seconds-since-epoch", Part::Other, self.timestamp.is_some()),
+            ("timezone-name", Part::Other, timezone_name),
+        ]
+        .into_iter()
+        .filter(|&(_, _, given)| given)
+        .map(|(name, part, _)| (name, part))
+    }
+
+    /// Fails with the first component given that isn't a part of `part`.
+    fn only(&self, part: Part) -> Result<(), ParseError> {
+        match self.components().find(|&(_, p)| p != part) {
+            Some((name, _)) => Err(ParseError::UnexpectedComponent(name)),
+            None => Ok(()),
+        }
+    }
+
+    /// The date, which needs the year and the day in it, but no others.
+    fn output_date(self) -> Result<Date, ParseError> {
+        self.only(Part::Date)?;
+        let given = self.given;
+        if !self
+            .components()
+            .any(|(name, _)| name == "year" || name == "iso-week-based-year")
+        {
+            return Err(ParseError::MissingComponent("year"));
+        }
+        let day_given = given.day_of_year.is_some()
+            || (given.month.is_some() && given.day.is_some())
+            || self.iso_week.is_some()
+            || (self.week_number.is_some() && self.weekday.is_some());
+        if !day_given {
+            return Err(ParseError::MissingComponent(if given.month.is_some() {
+                "day-of-month"
+            } else {
+                "month"
+            }));
+        }
+        self.output().map(|(date_time, _)| date_time.date())
+    }
+
+    /// The time, which needs the hour, but no others.
+    fn output_time(self) -> Result<Time, ParseError> {
+        self.only(Part::Time)?;
+        let given = self.given;
+        if given.hour.is_none() {
+            if given.hour_12.is_none() {
+                return Err(ParseError::MissingComponent("hour-of-day"));
+            }
+            if given.pm.is_none() {
+                return Err(ParseError::MissingComponent("am/pm"));
+            }
+        }
+        self.output().map(|(date_time, _)| date_time.time())
+    }
+
+    /// The offset, which is the only one it needs.
+    fn output_offset(self) -> Result<UtcOffset, ParseError> {
+        self.only(Part::Offset)?;
+        match self.zone {
+            Some(TimeZoneSpecifier::Offset(offset)) => Ok(offset),
+            _ => Err(ParseError::MissingComponent("offset")),
+        }
+    }
+
+    /// The fields given that disagree with `date_time`.
+    fn inconsistent_fields(
+        &self,
+        date_time: PrimitiveDateTime,
+    ) -> Result<Vec<&'static str>, ParseError> {
+        let calendar = self.locale.calendar();
+        let date = date_time.date();
+        let (year, month, day) = calendar.calendar_date(date);
+        let (iso_year, iso_week, _) = date.to_iso_week_date();
+        let hour = date_time.hour();
+        let given = self.given;
+        let iso_year_given = self.iso_year.get()?;
+        let checks = [
+            ("year", given.year.is_none_or(|y| y == year)),
+            (
+                "year-prefix",
+                given.year_prefix.is_none_or(|p| p == year.div_euclid(100)),
+            ),
+            (
+                "year-suffix",
+                given
+                    .year_suffix
+                    .is_none_or(|s| i32::from(s) == year.rem_euclid(100)),
+            ),
+            ("month", given.month.is_none_or(|m| m == month)),
+            ("day-of-month", given.day.is_none_or(|d| d == day)),
+            (
+                "day-of-year",
+                given
+                    .day_of_year
+                    .is_none_or(|d| d == calendar.ordinal(date)),
+            ),
+            (
+                "day-of-week",
+                self.weekday.is_none_or(|w| w == date.weekday()),
+            ),
+            (
+                "week-number",
+                given
+                    .sunday_week
+                    .is_none_or(|w| w == date.sunday_based_week())
+                    && given
+                        .monday_week
+                        .is_none_or(|w| w == date.monday_based_week()),
+            ),
+            (
+                "iso-week-number",
+                self.iso_week.is_none_or(|w| w == iso_week),
+            ),
+            (
+                "iso-week-based-year",
+                iso_year_given.is_none_or(|y| y == iso_year),
+            ),
+            ("hour-of-day", given.hour.is_none_or(|h| h == hour)),
+            (
+                "hour-of-half-day",
+                given.hour_12.is_none_or(|h| h == (hour + 11) % 12 + 1),
+            ),
+            ("am/pm", given.pm.is_none_or(|pm| pm == (hour >= 12))),
+        ];
+        Ok(checks
+            .into_iter()
+            .filter(|&(_, consistent)| !consistent)
+            .map(|(field, _)| field)
+            .collect())
     }
 }
 
-impl<'a> Collector for ParseCollector<'a> {
+impl<'a, 'l> Collector for ParseCollector<'a, 'l> {
     type Output = (PrimitiveDateTime, Option<TimeZoneSpecifier<'a>>);
     type Error = ParseError;
 
@@ -219,55 +1040,74 @@ impl<'a> Collector for ParseCollector<'a> {
     }
 
     #[inline]
-    fn day_of_week_name(&mut self) -> Result<(), Self::Error> {
-        let mut weekday = Weekday::Monday;
-        for _i in 0..7 {
-            let short = util::weekday_short_str(weekday);
-            if self.starts_with_ignore_ascii_case(short) {
-                let long = util::weekday_long_str(weekday);
-                if self.starts_with_ignore_ascii_case(long) {
-                    self.s = &self.s[long.len()..];
-                } else {
-                    self.s = &self.s[short.len()..];
-                }
-                // Found match. Ignore it!
-                return Ok(());
-            }
-            weekday = weekday.next();
-        }
-        Err(Self::Error::NotMatch("day of week name"))
+    fn day_of_week_name(&mut self, _spec: Spec) -> Result<(), Self::Error> {
+        let locale = self.locale;
+        let names = util::WEEKDAYS.into_iter().flat_map(|weekday| {
+            [
+                (locale.weekday_long(weekday), weekday),
+                (locale.weekday_short(weekday), weekday),
+            ]
+        });
+        let weekday = self
+            .consume_longest_name(names)
+            .ok_or(Self::Error::NotMatch("day of week name"))?;
+        self.weekday = Some(weekday);
+        Ok(())
     }
 
     #[inline]
-    fn month_name(&mut self) -> Result<(), Self::Error> {
-        let mut month = Month::January;
-        for _i in 0..12 {
-            let short = util::month_short_str(month);
-            if self.starts_with_ignore_ascii_case(short) {
-                let long = util::month_long_str(month);
-                if self.starts_with_ignore_ascii_case(long) {
-                    self.s = &self.s[long.len()..];
-                } else {
-                    self.s = &self.s[short.len()..];
-                }
-                match &mut self.day {
-                    ParsingDayOfYear::Unspecified => {
-                        self.day = ParsingDayOfYear::MonthDay(month, 1)
-                    }
-                    ParsingDayOfYear::MonthDay(current, _) => *current = month,
-                    // Prefer day of year over (month, day)
-                    ParsingDayOfYear::DayOfYear(_) => {}
-                }
-                return Ok(());
-            }
-            month = month.next();
+    fn month_name(&mut self, _spec: Spec) -> Result<(), Self::Error> {
+        let locale = self.locale;
+        let calendar = locale.calendar();
+        let names = util::MONTHS.into_iter().flat_map(|month| {
+            // The calendar's names, if any, replace all of the locale's.
+            let long = calendar.month_long(month);
+            let short = calendar.month_short(month);
+            [
+                (long.unwrap_or_else(|| locale.month_long(month)), month),
+                (short.unwrap_or_else(|| locale.month_short(month)), month),
+                (
+                    long.unwrap_or_else(|| locale.month_long_genitive(month)),
+                    month,
+                ),
+                (
+                    short.unwrap_or_else(|| locale.month_short_genitive(month)),
+                    month,
+                ),
+            ]
+        });
+        let month = self
+            .consume_longest_name(names)
+            .ok_or(Self::Error::NotMatch("month name"))?;
+        self.given.month = Some(month);
+        match &mut self.day {
+            ParsingDayOfYear::Unspecified => self.day = ParsingDayOfYear::MonthDay(month, 1),
+            ParsingDayOfYear::MonthDay(current, _) => *current = month,
+            // Prefer day of year over (month, day)
+            ParsingDayOfYear::DayOfYear(_) => {}
         }
-        Err(Self::Error::NotMatch("month name"))
+        Ok(())
+    }
+
+    #[inline]
+    fn preferred_date_time(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.expand(LocaleFormat::date_time(
+            spec.modifier == Some(Modifier::Era),
+        ))
     }
 
     #[inline]
-    fn year_prefix(&mut self) -> Result<(), Self::Error> {
-        let prefix = self.parse_int(2)?;
+    fn year_prefix(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        if self.has_era(spec) {
+            let names = self.locale.eras().iter().map(|era| (era.name, era));
+            self.era = Some(
+                self.consume_longest_name(names)
+                    .ok_or(Self::Error::NotMatch("era name"))?,
+            );
+            return Ok(());
+        }
+        let prefix = self.parse_int(spec, 2)?;
+        self.given.year_prefix = Some(prefix);
         match &mut self.year {
             ParsingYear::Unspecified => self.year = ParsingYear::PrefixSuffix(prefix, 0),
             // Prefer year over (year prefix, year suffix).
@@ -278,9 +1118,10 @@ impl<'a> Collector for ParseCollector<'a> {
     }
 
     #[inline]
-    fn day_of_month(&mut self) -> Result<(), Self::Error> {
-        let day = self.parse_nat(1, 2)?;
+    fn day_of_month(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let day = self.parse_nat(spec, 1, 2)?;
         if (1..=31).contains(&day) {
+            self.given.day = Some(day);
             match &mut self.day {
                 ParsingDayOfYear::Unspecified => {
                     self.day = ParsingDayOfYear::MonthDay(Month::January, day)
@@ -296,9 +1137,35 @@ impl<'a> Collector for ParseCollector<'a> {
     }
 
     #[inline]
-    fn hour_of_day(&mut self) -> Result<(), Self::Error> {
-        let hour = self.parse_nat(1, 2)?;
+    fn iso8601_week_based_year_suffix(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let y: u8 = self.parse_nat(spec, 1, 2)?;
+        if (0..100).contains(&y) {
+            match &mut self.iso_year {
+                ParsingYear::Unspecified => {
+                    self.iso_year = ParsingYear::PrefixSuffix(self.options.year_pivot.century(y), y)
+                }
+                // Prefer `%G` over `%g`.
+                ParsingYear::Year(_) => {}
+                ParsingYear::PrefixSuffix(_, current) => *current = y,
+            }
+            Ok(())
+        } else {
+            Err(Self::Error::ComponentOutOfRange("year"))
+        }
+    }
+
+    #[inline]
+    fn iso8601_week_based_year(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let y = self.parse_int(spec, 4)?;
+        self.iso_year = ParsingYear::Year(y);
+        Ok(())
+    }
+
+    #[inline]
+    fn hour_of_day(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let hour = self.parse_nat(spec, 1, 2)?;
         if (0..24).contains(&hour) {
+            self.given.hour = Some(hour);
             match &mut self.hour {
                 ParsingHour::Unspecified => self.hour = ParsingHour::FullDay(hour),
                 ParsingHour::FullDay(current) => *current = hour,
@@ -312,9 +1179,10 @@ impl<'a> Collector for ParseCollector<'a> {
     }
 
     #[inline]
-    fn hour_of_day_12(&mut self) -> Result<(), Self::Error> {
-        let hour: u8 = self.parse_nat(1, 2)?;
+    fn hour_of_day_12(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let hour: u8 = self.parse_nat(spec, 1, 2)?;
         if (1..=12).contains(&hour) {
+            self.given.hour_12 = Some(hour);
             let hour = hour % 12;
             match &mut self.hour {
                 ParsingHour::Unspecified => self.hour = ParsingHour::HalfDay(hour, false),
@@ -329,9 +1197,10 @@ impl<'a> Collector for ParseCollector<'a> {
     }
 
     #[inline]
-    fn day_of_year(&mut self) -> Result<(), Self::Error> {
-        let day = self.parse_nat(1, 3)?;
+    fn day_of_year(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let day = self.parse_nat(spec, 1, 3)?;
         if (1..=366).contains(&day) {
+            self.given.day_of_year = Some(day);
             // Prefer day of year over (month, day)
             self.day = ParsingDayOfYear::DayOfYear(day);
             Ok(())
@@ -341,10 +1210,11 @@ impl<'a> Collector for ParseCollector<'a> {
     }
 
     #[inline]
-    fn month_of_year(&mut self) -> Result<(), Self::Error> {
-        let month = self.parse_nat(1, 2)?;
+    fn month_of_year(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let month = self.parse_nat(spec, 1, 2)?;
         if (1..=12).contains(&month) {
             let month = util::get_month(month).unwrap();
+            self.given.month = Some(month);
             match &mut self.day {
                 ParsingDayOfYear::Unspecified => self.day = ParsingDayOfYear::MonthDay(month, 1),
                 ParsingDayOfYear::MonthDay(current, _) => *current = month,
@@ -358,9 +1228,10 @@ impl<'a> Collector for ParseCollector<'a> {
     }
 
     #[inline]
-    fn minute_of_hour(&mut self) -> Result<(), Self::Error> {
-        let minute = self.parse_nat(1, 2)?;
+    fn minute_of_hour(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let minute = self.parse_nat(spec, 1, 2)?;
         if (0..60).contains(&minute) {
+            self.given.minute = Some(minute);
             self.minute = minute;
             Ok(())
         } else {
@@ -369,28 +1240,48 @@ impl<'a> Collector for ParseCollector<'a> {
     }
 
     #[inline]
-    fn ampm(&mut self) -> Result<(), Self::Error> {
-        for h in [0, 12] {
-            let s = util::ampm_lower(h);
-            if self.starts_with_ignore_ascii_case(s) {
-                match &mut self.hour {
-                    ParsingHour::Unspecified => self.hour = ParsingHour::HalfDay(0, h != 0),
-                    // Prefer full day over halfday + am/pm.
-                    ParsingHour::FullDay(_) => {}
-                    ParsingHour::HalfDay(_, current) => *current = h != 0,
-                }
-                // Consume AM/PM substring
-                self.s = &self.s[2..];
-                return Ok(());
-            }
+    fn ampm(&mut self, _spec: Spec) -> Result<(), Self::Error> {
+        let locale = self.locale;
+        let pm = self
+            .consume_longest_name([(locale.am(), false), (locale.pm(), true)])
+            .ok_or(Self::Error::NotMatch("am/pm"))?;
+        self.given.pm = Some(pm);
+        match &mut self.hour {
+            ParsingHour::Unspecified => self.hour = ParsingHour::HalfDay(0, pm),
+            // Prefer full day over halfday + am/pm.
+            ParsingHour::FullDay(_) => {}
+            ParsingHour::HalfDay(_, current) => *current = pm,
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn time_ampm(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        // Keep accepting spaces around the colons as the POSIX locale always did.
+        if self.locale.t_fmt_ampm() == PosixLocale.t_fmt_ampm() {
+            desc_parser::posix_time_ampm(self, spec)
+        } else {
+            self.expand(LocaleFormat::TimeAmPm)
         }
-        Err(Self::Error::NotMatch("am/pm"))
     }
 
     #[inline]
-    fn second_of_minute(&mut self) -> Result<(), Self::Error> {
-        let second = self.parse_nat(1, 2)?;
+    fn seconds_since_epoch(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let precision = timestamp_precision(spec)?;
+        // Enough for any representable date time, while the value itself never overflows.
+        let value: i128 = self.parse_int(spec, 31)?;
+        let nanos = value
+            .checked_mul(10i128.pow(9 - precision))
+            .ok_or(Self::Error::ComponentOutOfRange("seconds-since-epoch"))?;
+        self.timestamp = Some(nanos);
+        Ok(())
+    }
+
+    #[inline]
+    fn second_of_minute(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let second = self.parse_nat(spec, 1, 2)?;
         if (0..61).contains(&second) {
+            self.given.second = Some(second);
             self.second = second;
             Ok(())
         } else {
@@ -399,11 +1290,21 @@ impl<'a> Collector for ParseCollector<'a> {
     }
 
     #[inline]
-    fn nanosecond_of_second(&mut self) -> Result<(), Self::Error> {
-        let input_length = self.s.len();
-        let nanosecond: u32 = self.parse_nat(1, 9)?;
+    fn nanosecond_of_second(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let digits = subsecond_digits(spec)?;
+        let alt_digits = match self.alt_digits(spec) {
+            Some(AltDigits::Positional(alt_digits)) => Some(alt_digits),
+            // Fractions are not numbers from 0.
+            Some(AltDigits::Table(_)) | None => None,
+        };
+        let spec = Spec {
+            modifier: alt_digits.and(spec.modifier),
+            ..spec
+        };
+        let input = self.s;
+        let nanosecond: u32 = self.parse_nat(spec, 1, digits)?;
 
-        let digits_consumed = input_length - self.s.len();
+        let digits_consumed = input[..input.len() - self.s.len()].chars().count();
         static SCALE: [u32; 10] = [
             0,
             100_000_000,
@@ -417,36 +1318,45 @@ impl<'a> Collector for ParseCollector<'a> {
             1,
         ];
         self.nanosecond = nanosecond * SCALE[digits_consumed];
+        self.given.nanosecond = Some(self.nanosecond);
+        self.given.subsecond_digits = Some(digits_consumed as u8);
 
+        // Consume the rest of the digits, but note that they are truncated.
+        while let Some((_, len)) = Self::leading_digit(self.s, alt_digits) {
+            self.s = &self.s[len..];
+            self.truncated_subsecond = true;
+        }
         Ok(())
     }
 
     #[inline]
-    fn week_number_of_current_year_start_sunday(&mut self) -> Result<(), Self::Error> {
-        let w: u8 = self.parse_nat(1, 2)?;
-        if (0..=53).contains(&w) {
-            // Ignore it!
+    fn day_of_week_from_monday_as_1(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let w: u8 = self.parse_nat(spec, 1, 1)?;
+        if (1..=7).contains(&w) {
+            self.weekday = Some(util::WEEKDAYS[(w - 1) as usize]);
             Ok(())
         } else {
-            Err(Self::Error::ComponentOutOfRange("week-number"))
+            Err(Self::Error::ComponentOutOfRange("day-of-week"))
         }
     }
 
     #[inline]
-    fn day_of_week_from_sunday_as_0(&mut self) -> Result<(), Self::Error> {
-        let w: u8 = self.parse_nat(1, 1)?;
-        if (0..7).contains(&w) {
-            // Ignore it!
+    fn week_number_of_current_year_start_sunday(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let w: u8 = self.parse_nat(spec, 1, 2)?;
+        if (0..=53).contains(&w) {
+            self.given.sunday_week = Some(w);
+            self.week_number = Some((w, Weekday::Sunday));
             Ok(())
         } else {
-            Err(Self::Error::ComponentOutOfRange("day-of-week"))
+            Err(Self::Error::ComponentOutOfRange("week-number"))
         }
     }
 
     #[inline]
-    fn week_number_of_current_year_start_monday(&mut self) -> Result<(), Self::Error> {
-        let w: u8 = self.parse_nat(1, 2)?;
-        if (0..=53).contains(&w) {
+    fn iso8601_week_number(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let w: u8 = self.parse_nat(spec, 1, 2)?;
+        if (1..=53).contains(&w) {
+            self.iso_week = Some(w);
             Ok(())
         } else {
             Err(Self::Error::ComponentOutOfRange("week-number"))
@@ -454,33 +1364,84 @@ impl<'a> Collector for ParseCollector<'a> {
     }
 
     #[inline]
-    fn year_suffix(&mut self) -> Result<(), Self::Error> {
-        let y = self.parse_nat(1, 2)?;
-        if (0..100).contains(&y) {
-            match &mut self.year {
-                ParsingYear::Unspecified => {
-                    self.year = ParsingYear::PrefixSuffix(if y < 69 { 20 } else { 19 }, y)
-                }
-                // Prefer year over (year prefix, year suffix).
-                ParsingYear::Year(_) => {}
-                ParsingYear::PrefixSuffix(_, current) => *current = y,
-            }
+    fn day_of_week_from_sunday_as_0(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let w: u8 = self.parse_nat(spec, 1, 1)?;
+        if (0..7).contains(&w) {
+            self.weekday = Some(util::WEEKDAYS[((w + 6) % 7) as usize]);
             Ok(())
         } else {
-            Err(Self::Error::ComponentOutOfRange("year-suffix"))
+            Err(Self::Error::ComponentOutOfRange("day-of-week"))
         }
     }
 
     #[inline]
-    fn year(&mut self) -> Result<(), Self::Error> {
-        let y = self.parse_int(4)?;
-        // Prefer year over (year prefix, year suffix).
-        self.year = ParsingYear::Year(y);
-        Ok(())
+    fn week_number_of_current_year_start_monday(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let w: u8 = self.parse_nat(spec, 1, 2)?;
+        if (0..=53).contains(&w) {
+            self.given.monday_week = Some(w);
+            self.week_number = Some((w, Weekday::Monday));
+            Ok(())
+        } else {
+            Err(Self::Error::ComponentOutOfRange("week-number"))
+        }
+    }
+
+    #[inline]
+    fn preferred_date(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.expand(LocaleFormat::date(spec.modifier == Some(Modifier::Era)))
+    }
+
+    #[inline]
+    fn preferred_time_of_day(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.expand(LocaleFormat::time(spec.modifier == Some(Modifier::Era)))
+    }
+
+    #[inline]
+    fn year_suffix(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let has_era = self.has_era(spec);
+        let y = if has_era {
+            let y: u16 = self.parse_nat(spec, 1, 4)?;
+            self.year_of_era = Some(y.into());
+            // Works as `%y` if no era is given, as glibc does.
+            if y >= 100 {
+                return Ok(());
+            }
+            y as u8
+        } else {
+            let y = self.parse_nat(spec, 1, 2)?;
+            self.given.year_suffix = Some(y);
+            y
+        };
+        if (0..100).contains(&y) {
+            match &mut self.year {
+                ParsingYear::Unspecified => {
+                    self.year = ParsingYear::PrefixSuffix(self.options.year_pivot.century(y), y)
+                }
+                // Prefer year over (year prefix, year suffix).
+                ParsingYear::Year(_) => {}
+                ParsingYear::PrefixSuffix(_, current) => *current = y,
+            }
+            Ok(())
+        } else {
+            Err(Self::Error::ComponentOutOfRange("year-suffix"))
+        }
+    }
+
+    #[inline]
+    fn year(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        // Falls back to the plain year if no era matches, as glibc does.
+        if self.has_era(spec) && self.era_year()? {
+            return Ok(());
+        }
+        let y = self.parse_int(spec, 4)?;
+        self.given.year = Some(y);
+        // Prefer year over (year prefix, year suffix).
+        self.year = ParsingYear::Year(y);
+        Ok(())
     }
 
     #[inline]
-    fn timezone(&mut self) -> Result<(), Self::Error> {
+    fn timezone(&mut self, _spec: Spec) -> Result<(), Self::Error> {
         let negate = match self.peek_byte() {
             Some(b'Z') => {
                 self.s = &self.s[1..]; // skip Z
@@ -494,24 +1455,27 @@ impl<'a> Collector for ParseCollector<'a> {
             Some(b) => return Err(Self::Error::UnexpectedByte("+ or -", b)),
             None => return Err(Self::Error::UnexpectedEnd("+ or -")),
         };
-        let h: u8 = self.parse_nat(2, 2)?;
-        if self.peek_byte() == Some(b':') {
-            self.s = &self.s[1..]; // skip :
-        }
-        let m: u8 = self.parse_nat(2, 2)?;
-        let h: i8 = h
-            .try_into()
-            .map_err(|_| Self::Error::ComponentOutOfRange("offset-hour"))?;
-        let m: i8 = m
-            .try_into()
-            .map_err(|_| Self::Error::ComponentOutOfRange("offset-minute"))?;
-        let (h, m) = if negate { (-h, -m) } else { (h, m) };
-        self.zone = Some(TimeZoneSpecifier::Offset(UtcOffset::from_hms(h, m, 0)?));
+        let h: u8 = self.parse_nat(Spec::default(), 2, 2)?;
+        let colon = self.peek_byte() == Some(b':');
+        let m = self.parse_offset_component(colon)?;
+        let sec = if m.is_some() {
+            self.parse_offset_component(colon)?
+        } else {
+            None
+        };
+        let to_i8 = |v: u8, component| {
+            i8::try_from(v).map_err(|_| Self::Error::ComponentOutOfRange(component))
+        };
+        let h = to_i8(h, "offset-hour")?;
+        let m = to_i8(m.unwrap_or(0), "offset-minute")?;
+        let sec = to_i8(sec.unwrap_or(0), "offset-second")?;
+        let (h, m, sec) = if negate { (-h, -m, -sec) } else { (h, m, sec) };
+        self.zone = Some(TimeZoneSpecifier::Offset(UtcOffset::from_hms(h, m, sec)?));
         Ok(())
     }
 
     #[inline]
-    fn timezone_name(&mut self) -> Result<(), Self::Error> {
+    fn timezone_name(&mut self, _spec: Spec) -> Result<(), Self::Error> {
         let s = self.get_until_whitespace();
         self.zone = Some(TimeZoneSpecifier::Name(s));
         Ok(())
@@ -548,8 +1512,11 @@ impl<'a> Collector for ParseCollector<'a> {
 
     #[inline]
     fn unconsumed_input(&self) -> Result<(), Self::Error> {
+        if self.truncated_subsecond {
+            return Err(Self::Error::TruncatedDigits("fractional second"));
+        }
         let unconsumed_input = self.s.to_string();
-        if unconsumed_input.len() > 0 {
+        if !unconsumed_input.is_empty() {
             Err(Self::Error::UnconvertedDataRemains(unconsumed_input))
         } else {
             Ok(())
@@ -558,33 +1525,14 @@ impl<'a> Collector for ParseCollector<'a> {
 
     #[inline]
     fn output(self) -> Result<Self::Output, Self::Error> {
-        let year = match self.year {
-            ParsingYear::Unspecified => 1900,
-            ParsingYear::Year(y) => y,
-            ParsingYear::PrefixSuffix(p, s) => p
-                .checked_mul(100)
-                .and_then(|p| p.checked_add(s as i32))
-                .ok_or(Self::Error::ComponentOutOfRange("year"))?,
-        };
-        let date = match self.day {
-            ParsingDayOfYear::Unspecified => Date::from_ordinal_date(year, 1)?,
-            ParsingDayOfYear::MonthDay(month, day) => Date::from_calendar_date(year, month, day)?,
-            ParsingDayOfYear::DayOfYear(day) => Date::from_ordinal_date(year, day)?,
-        };
-        let hour = match self.hour {
-            ParsingHour::Unspecified => 0,
-            ParsingHour::FullDay(h) => h,
-            ParsingHour::HalfDay(h, ampm) => {
-                if ampm {
-                    h + 12
-                } else {
-                    h
-                }
+        let (date_time, zone) = self.resolve()?;
+        if self.options.check_consistency {
+            let fields = self.inconsistent_fields(date_time)?;
+            if !fields.is_empty() {
+                return Err(Self::Error::Inconsistent(fields));
             }
-        };
-        let time = Time::from_hms_nano(hour, self.minute, self.second, self.nanosecond)?;
-        let zone = self.zone;
-        Ok((PrimitiveDateTime::new(date, time), zone))
+        }
+        Ok((date_time, zone))
     }
 }
 
@@ -604,13 +1552,290 @@ pub fn parse_strict_date_time_maybe_with_zone<'a>(
     desc_parser::parse_format_specifications(fmt, collector, true)
 }
 
+pub fn parse_date_time_maybe_with_zone_with_locale<'a>(
+    fmt: &str,
+    s: &'a str,
+    locale: &dyn Locale,
+    options: &ParseOptions,
+) -> Result<(PrimitiveDateTime, Option<TimeZoneSpecifier<'a>>), ParseError> {
+    let collector = ParseCollector::new(s)
+        .with_locale(locale)
+        .with_options(*options);
+    desc_parser::parse_format_specifications(fmt, collector, false)
+}
+
+pub fn parse_strict_date_time_maybe_with_zone_with_locale<'a>(
+    fmt: &str,
+    s: &'a str,
+    locale: &dyn Locale,
+    options: &ParseOptions,
+) -> Result<(PrimitiveDateTime, Option<TimeZoneSpecifier<'a>>), ParseError> {
+    let collector = ParseCollector::new(s)
+        .with_locale(locale)
+        .with_options(*options);
+    desc_parser::parse_format_specifications(fmt, collector, true)
+}
+
+/// Parses `s` and gets the result with `output` instead of `Collector::output`.
+fn parse_with<'a, 'l, T>(
+    fmt: &str,
+    s: &'a str,
+    strict: bool,
+    locale: &'l dyn Locale,
+    options: &ParseOptions,
+    output: impl FnOnce(ParseCollector<'a, 'l>) -> Result<T, ParseError>,
+) -> Result<T, ParseError> {
+    let mut collector = ParseCollector::new(s)
+        .with_locale(locale)
+        .with_options(*options);
+    desc_parser::collect(fmt, &mut collector)?;
+    if strict {
+        collector.unconsumed_input()?;
+    }
+    output(collector)
+}
+
+/// Parses into a `Date`, failing if there are components other than those of the date, or if the
+/// year or the day in it is missing, instead of defaulting to 1900-01-01.
+///
+/// ```
+/// use time::macros::date;
+/// use time_fmt::parse::{parse_date, ParseError};
+///
+/// assert_eq!(parse_date("%Y-%m-%d", "2022-03-06").unwrap(), date!(2022 - 03 - 06));
+/// assert_eq!(
+///     parse_date("%m-%d", "03-06"),
+///     Err(ParseError::MissingComponent("year"))
+/// );
+/// assert_eq!(
+///     parse_date("%F %T", "2022-03-06 12:34:56"),
+///     Err(ParseError::UnexpectedComponent("hour-of-day"))
+/// );
+/// ```
+pub fn parse_date(fmt: &str, s: &str) -> Result<Date, ParseError> {
+    parse_date_with_locale(fmt, s, &PosixLocale, &ParseOptions::default())
+}
+
+pub fn parse_strict_date(fmt: &str, s: &str) -> Result<Date, ParseError> {
+    parse_strict_date_with_locale(fmt, s, &PosixLocale, &ParseOptions::default())
+}
+
+pub fn parse_date_with_locale(
+    fmt: &str,
+    s: &str,
+    locale: &dyn Locale,
+    options: &ParseOptions,
+) -> Result<Date, ParseError> {
+    parse_with(fmt, s, false, locale, options, ParseCollector::output_date)
+}
+
+pub fn parse_strict_date_with_locale(
+    fmt: &str,
+    s: &str,
+    locale: &dyn Locale,
+    options: &ParseOptions,
+) -> Result<Date, ParseError> {
+    parse_with(fmt, s, true, locale, options, ParseCollector::output_date)
+}
+
+/// Parses into a `Time`, failing if there are components other than those of the time, or if the
+/// hour is missing. The minute and others default to 0.
+pub fn parse_time(fmt: &str, s: &str) -> Result<Time, ParseError> {
+    parse_time_with_locale(fmt, s, &PosixLocale, &ParseOptions::default())
+}
+
+pub fn parse_strict_time(fmt: &str, s: &str) -> Result<Time, ParseError> {
+    parse_strict_time_with_locale(fmt, s, &PosixLocale, &ParseOptions::default())
+}
+
+pub fn parse_time_with_locale(
+    fmt: &str,
+    s: &str,
+    locale: &dyn Locale,
+    options: &ParseOptions,
+) -> Result<Time, ParseError> {
+    parse_with(fmt, s, false, locale, options, ParseCollector::output_time)
+}
+
+pub fn parse_strict_time_with_locale(
+    fmt: &str,
+    s: &str,
+    locale: &dyn Locale,
+    options: &ParseOptions,
+) -> Result<Time, ParseError> {
+    parse_with(fmt, s, true, locale, options, ParseCollector::output_time)
+}
+
+/// Parses into a `UtcOffset` with `%z`, failing if there are any other components.
+pub fn parse_offset(fmt: &str, s: &str) -> Result<UtcOffset, ParseError> {
+    parse_offset_with_locale(fmt, s, &PosixLocale, &ParseOptions::default())
+}
+
+pub fn parse_strict_offset(fmt: &str, s: &str) -> Result<UtcOffset, ParseError> {
+    parse_strict_offset_with_locale(fmt, s, &PosixLocale, &ParseOptions::default())
+}
+
+pub fn parse_offset_with_locale(
+    fmt: &str,
+    s: &str,
+    locale: &dyn Locale,
+    options: &ParseOptions,
+) -> Result<UtcOffset, ParseError> {
+    parse_with(
+        fmt,
+        s,
+        false,
+        locale,
+        options,
+        ParseCollector::output_offset,
+    )
+}
+
+pub fn parse_strict_offset_with_locale(
+    fmt: &str,
+    s: &str,
+    locale: &dyn Locale,
+    options: &ParseOptions,
+) -> Result<UtcOffset, ParseError> {
+    parse_with(fmt, s, true, locale, options, ParseCollector::output_offset)
+}
+
+/// Parses into the fields as given, leaving the others `None`, so that they can be defaulted in
+/// any way.
+///
+/// ```
+/// use time::{macros::datetime, Month};
+/// use time_fmt::parse::parse_fields;
+///
+/// let mut parsed = parse_fields("%b %e %T", "Mar  6 12:34:56").unwrap();
+/// assert_eq!(parsed.month, Some(Month::March));
+/// assert_eq!(parsed.year, None);
+/// parsed.year = Some(2022);
+/// assert_eq!(
+///     parsed.to_primitive_date_time().unwrap(),
+///     datetime!(2022-03-06 12:34:56)
+/// );
+/// ```
+pub fn parse_fields<'a>(fmt: &str, s: &'a str) -> Result<Parsed<'a>, ParseError> {
+    parse_fields_with_locale(fmt, s, &PosixLocale, &ParseOptions::default())
+}
+
+pub fn parse_strict_fields<'a>(fmt: &str, s: &'a str) -> Result<Parsed<'a>, ParseError> {
+    parse_strict_fields_with_locale(fmt, s, &PosixLocale, &ParseOptions::default())
+}
+
+pub fn parse_fields_with_locale<'a>(
+    fmt: &str,
+    s: &'a str,
+    locale: &'a dyn Locale,
+    options: &ParseOptions,
+) -> Result<Parsed<'a>, ParseError> {
+    parse_with(fmt, s, false, locale, options, |collector| {
+        collector.parsed()
+    })
+}
+
+pub fn parse_strict_fields_with_locale<'a>(
+    fmt: &str,
+    s: &'a str,
+    locale: &'a dyn Locale,
+    options: &ParseOptions,
+) -> Result<Parsed<'a>, ParseError> {
+    parse_with(fmt, s, true, locale, options, |collector| {
+        collector.parsed()
+    })
+}
+
+/// `date_time` at the offset `zone` gives, or `default_offset` if `zone` is `None`. The offset of a
+/// timezone name is given by `resolve`, which gets the name and `date_time`.
+fn assume_zone(
+    date_time: PrimitiveDateTime,
+    zone: Option<TimeZoneSpecifier<'_>>,
+    default_offset: Option<UtcOffset>,
+    resolve: impl FnOnce(&str, PrimitiveDateTime) -> Option<UtcOffset>,
+) -> Result<OffsetDateTime, ParseError> {
+    let offset = match zone {
+        Some(TimeZoneSpecifier::Offset(offset)) => offset,
+        Some(TimeZoneSpecifier::Name(name)) => {
+            resolve(name, date_time).ok_or_else(|| ParseError::UnknownTimeZone(name.to_string()))?
+        }
+        None => default_offset.ok_or(ParseError::MissingOffset)?,
+    };
+    Ok(date_time.assume_offset(offset))
+}
+
+/// Parses into an `OffsetDateTime` at the offset `%z` gives, or the one `resolve` gives for the
+/// timezone name `%Z` gives and the local date time. Without either, `default_offset` is used,
+/// or it fails with [`ParseError::MissingOffset`] if that's `None` too.
+///
+/// ```
+/// use time::macros::{datetime, offset};
+/// use time_fmt::parse::parse_offset_date_time;
+///
+/// let resolve = |name: &str, _| (name == "JST").then(|| offset!(+9));
+/// assert_eq!(
+///     parse_offset_date_time("%F %T %Z", "2022-03-06 12:34:56 JST", None, resolve).unwrap(),
+///     datetime!(2022-03-06 12:34:56 +9)
+/// );
+/// assert_eq!(
+///     parse_offset_date_time("%F %T", "2022-03-06 12:34:56", Some(offset!(UTC)), resolve)
+///         .unwrap(),
+///     datetime!(2022-03-06 12:34:56 UTC)
+/// );
+/// ```
+pub fn parse_offset_date_time(
+    fmt: &str,
+    s: &str,
+    default_offset: Option<UtcOffset>,
+    resolve: impl FnOnce(&str, PrimitiveDateTime) -> Option<UtcOffset>,
+) -> Result<OffsetDateTime, ParseError> {
+    let (date_time, zone) = parse_date_time_maybe_with_zone(fmt, s)?;
+    assume_zone(date_time, zone, default_offset, resolve)
+}
+
+pub fn parse_strict_offset_date_time(
+    fmt: &str,
+    s: &str,
+    default_offset: Option<UtcOffset>,
+    resolve: impl FnOnce(&str, PrimitiveDateTime) -> Option<UtcOffset>,
+) -> Result<OffsetDateTime, ParseError> {
+    let (date_time, zone) = parse_strict_date_time_maybe_with_zone(fmt, s)?;
+    assume_zone(date_time, zone, default_offset, resolve)
+}
+
+pub fn parse_offset_date_time_with_locale(
+    fmt: &str,
+    s: &str,
+    default_offset: Option<UtcOffset>,
+    resolve: impl FnOnce(&str, PrimitiveDateTime) -> Option<UtcOffset>,
+    locale: &dyn Locale,
+    options: &ParseOptions,
+) -> Result<OffsetDateTime, ParseError> {
+    let (date_time, zone) = parse_date_time_maybe_with_zone_with_locale(fmt, s, locale, options)?;
+    assume_zone(date_time, zone, default_offset, resolve)
+}
+
+pub fn parse_strict_offset_date_time_with_locale(
+    fmt: &str,
+    s: &str,
+    default_offset: Option<UtcOffset>,
+    resolve: impl FnOnce(&str, PrimitiveDateTime) -> Option<UtcOffset>,
+    locale: &dyn Locale,
+    options: &ParseOptions,
+) -> Result<OffsetDateTime, ParseError> {
+    let (date_time, zone) =
+        parse_strict_date_time_maybe_with_zone_with_locale(fmt, s, locale, options)?;
+    assume_zone(date_time, zone, default_offset, resolve)
+}
+
 #[cfg(test)]
 mod tests {
     use super::{
-        parse_date_time_maybe_with_zone, parse_strict_date_time_maybe_with_zone, ParseError,
-        TimeZoneSpecifier,
+        parse_date, parse_date_time_maybe_with_zone, parse_offset, parse_offset_date_time,
+        parse_strict_date, parse_strict_date_time_maybe_with_zone, parse_strict_offset_date_time,
+        parse_time, ParseError, TimeZoneSpecifier,
     };
-    use time::macros::{datetime, offset};
+    use time::macros::{datetime, offset, time};
 
     #[test]
     fn test_simple_parse() -> Result<(), super::ParseError> {
@@ -706,6 +1931,10 @@ mod tests {
             parse_date_time_maybe_with_zone("%r %F", "12:34:56 AM 2022-03-06")?,
             (datetime!(2022-03-06 00:34:56), None)
         );
+        assert_eq!(
+            parse_date_time_maybe_with_zone("%r", "12 : 34 :56 PM")?,
+            (datetime!(1900-01-01 12:34:56), None)
+        );
         assert_eq!(
             parse_date_time_maybe_with_zone("%R", "12: 4")?,
             (datetime!(1900-01-01 12:04:00), None)
@@ -769,6 +1998,611 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_subsecond_digits() -> Result<(), super::ParseError> {
+        assert_eq!(
+            parse_date_time_maybe_with_zone("%T.%3f", "12:34:56.12")?,
+            (datetime!(1900-01-01 12:34:56.12), None)
+        );
+        assert_eq!(
+            parse_date_time_maybe_with_zone("%T.%3N %Y", "12:34:56.123456 2022")?,
+            (datetime!(2022-01-01 12:34:56.123), None)
+        );
+        assert_eq!(
+            parse_date_time_maybe_with_zone("%T.%f", "12:34:56.1234567891")?,
+            (datetime!(1900-01-01 12:34:56.123456789), None)
+        );
+        assert_eq!(
+            parse_strict_date_time_maybe_with_zone("%T.%6f", "12:34:56.123456")?,
+            (datetime!(1900-01-01 12:34:56.123456), None)
+        );
+        assert_eq!(
+            parse_strict_date_time_maybe_with_zone("%T.%6f", "12:34:56.1234567"),
+            Err(ParseError::TruncatedDigits("fractional second"))
+        );
+        assert_eq!(
+            parse_date_time_maybe_with_zone("%T.%10f", "12:34:56.1"),
+            Err(ParseError::UnsupportedWidth('f', 10))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_seconds_since_epoch() -> Result<(), super::ParseError> {
+        let utc = || Some(TimeZoneSpecifier::Offset(offset!(UTC)));
+        assert_eq!(
+            parse_date_time_maybe_with_zone("%s", "1646570096")?,
+            (datetime!(2022-03-06 12:34:56), utc())
+        );
+        assert_eq!(
+            parse_date_time_maybe_with_zone("%s", "+1646570096")?,
+            (datetime!(2022-03-06 12:34:56), utc())
+        );
+        assert_eq!(
+            parse_date_time_maybe_with_zone("%s.%f", "1646570096.789")?,
+            (datetime!(2022-03-06 12:34:56.789), utc())
+        );
+        assert_eq!(
+            parse_date_time_maybe_with_zone("%.3s", "1646570096789")?,
+            (datetime!(2022-03-06 12:34:56.789), utc())
+        );
+        assert_eq!(
+            parse_date_time_maybe_with_zone("%.9s", "1646570096789012345")?,
+            (datetime!(2022-03-06 12:34:56.789012345), utc())
+        );
+        assert_eq!(
+            parse_date_time_maybe_with_zone("%s.%f", "-2.5")?,
+            (datetime!(1969-12-31 23:59:58.5), utc())
+        );
+        assert_eq!(
+            parse_date_time_maybe_with_zone("%s %z", "1646570096 +0900")?,
+            (
+                datetime!(2022-03-06 21:34:56),
+                Some(TimeZoneSpecifier::Offset(offset!(+9:00)))
+            )
+        );
+        assert!(matches!(
+            parse_date_time_maybe_with_zone("%s", "99999999999999999999"),
+            Err(ParseError::ComponentRange(_))
+        ));
+        assert_eq!(
+            parse_date_time_maybe_with_zone("%.3d", "1"),
+            Err(ParseError::UnknownSpecifier('.'))
+        );
+        assert_eq!(
+            parse_date_time_maybe_with_zone("%s", "9999999999999999999999999999999"),
+            Err(ParseError::ComponentOutOfRange("seconds-since-epoch"))
+        );
+        assert!(matches!(
+            parse_date_time_maybe_with_zone("%.9s", "-9999999999999999999999999999999"),
+            Err(ParseError::ComponentRange(_))
+        ));
+        assert_eq!(
+            parse_date_time_maybe_with_zone("%s", "x"),
+            Err(ParseError::UnexpectedByte("digits or sign", b'x'))
+        );
+        assert_eq!(
+            parse_date_time_maybe_with_zone("%s", "-"),
+            Err(ParseError::UnexpectedEnd("digits"))
+        );
+        assert_eq!(
+            parse_date_time_maybe_with_zone("%s ", "+ "),
+            Err(ParseError::UnexpectedByte("digits", b' '))
+        );
+        assert_eq!(
+            parse_date_time_maybe_with_zone("%Y", "-"),
+            Err(ParseError::UnexpectedEnd("digits"))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_modifiers() -> Result<(), super::ParseError> {
+        assert_eq!(
+            parse_date_time_maybe_with_zone("%Ec", "Sun Mar  6 12:34:56 2022")?,
+            (datetime!(2022-03-06 12:34:56), None)
+        );
+        assert_eq!(
+            parse_date_time_maybe_with_zone("%EY-%Om-%Od %OH:%OM:%OS", "2022-03-06 12:34:56")?,
+            (datetime!(2022-03-06 12:34:56), None)
+        );
+        assert_eq!(
+            parse_date_time_maybe_with_zone("%EC%Ey %Ex %EX", "2022 3/6/22 12:34:56")?,
+            (datetime!(2022-03-06 12:34:56), None)
+        );
+        assert_eq!(
+            parse_date_time_maybe_with_zone("%Eq", "2022"),
+            Err(ParseError::UnknownSpecifier('q'))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_iso8601_week_date() -> Result<(), Box<dyn std::error::Error>> {
+        for (fmt, s, expected) in [
+            ("%G-W%V-%u", "2022-W09-7", datetime!(2022-03-06 00:00)),
+            ("%G-W%V-%u", "2020-W53-5", datetime!(2021-01-01 00:00)),
+            ("%G-W%V-%u", "2025-W01-1", datetime!(2024-12-30 00:00)),
+            // Monday unless given.
+            ("%G-W%V", "2022-W09", datetime!(2022-02-28 00:00)),
+            ("%g %V %u", "21 1 1", datetime!(2021-01-04 00:00)),
+            // The year is the ISO 8601 week-based year unless given.
+            ("%Y %V", "2022 01", datetime!(2022-01-03 00:00)),
+            ("%G %Y %V", "2021 2022 01", datetime!(2021-01-04 00:00)),
+            // The month and the day take precedence.
+            ("%F %V %u", "2022-03-06 01 1", datetime!(2022-03-06 00:00)),
+        ] {
+            assert_eq!(
+                parse_strict_date_time_maybe_with_zone(fmt, s)?,
+                (expected, None),
+                "{} {}",
+                fmt,
+                s
+            );
+        }
+        assert!(matches!(
+            parse_date_time_maybe_with_zone("%G-W%V-%u", "2021-W53-1"),
+            Err(ParseError::ComponentRange(_))
+        ));
+        assert_eq!(
+            parse_date_time_maybe_with_zone("%G-W%V-%u", "2021-W01-8"),
+            Err(ParseError::ComponentOutOfRange("day-of-week"))
+        );
+        let mut dt = datetime!(2019-12-20 00:00);
+        while dt < datetime!(2021-01-10 00:00) {
+            let s = crate::format::format_date_time("%G-W%V-%u", dt)?;
+            assert_eq!(
+                parse_date_time_maybe_with_zone("%G-W%V-%u", &s)?,
+                (dt, None)
+            );
+            dt += time::Duration::DAY;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_week_number() -> Result<(), Box<dyn std::error::Error>> {
+        for (fmt, s, expected) in [
+            ("%Y %U %a", "2022 10 Sun", datetime!(2022-03-06 00:00)),
+            ("%Y %W %w", "2022 09 0", datetime!(2022-03-06 00:00)),
+            // Ignored without the weekday.
+            ("%Y %U", "2022 10", datetime!(2022-01-01 00:00)),
+            // Days before the first week.
+            ("%Y %U %a", "2022 00 Sat", datetime!(2022-01-01 00:00)),
+            ("%Y %U %a", "2022 00 Fri", datetime!(2021-12-31 00:00)),
+            // The month and the day take precedence.
+            ("%F %U %a", "2022-03-06 01 Mon", datetime!(2022-03-06 00:00)),
+        ] {
+            assert_eq!(
+                parse_strict_date_time_maybe_with_zone(fmt, s)?,
+                (expected, None),
+                "{} {}",
+                fmt,
+                s
+            );
+        }
+        for fmt in ["%Y %U %w", "%Y %W %a", "%y %W %u", "%Y %U %A"] {
+            let mut dt = datetime!(2020-12-20 00:00);
+            while dt < datetime!(2022-01-10 00:00) {
+                let s = crate::format::format_date_time(fmt, dt)?;
+                assert_eq!(
+                    parse_date_time_maybe_with_zone(fmt, &s)?,
+                    (dt, None),
+                    "{}",
+                    s
+                );
+                dt += time::Duration::DAY;
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_offset_date_time() -> Result<(), super::ParseError> {
+        use super::{
+            parse_offset_date_time_with_locale, parse_strict_offset_date_time_with_locale,
+            ParseOptions, StrptimeFormat,
+        };
+        use crate::{
+            calendar::SolarHijri,
+            locale::{PosixLocale, WithCalendar},
+        };
+
+        fn resolve(name: &str, date_time: time::PrimitiveDateTime) -> Option<time::UtcOffset> {
+            match name {
+                "UTC" => Some(offset!(UTC)),
+                "CET" if date_time.month() == time::Month::July => Some(offset!(+2)),
+                "CET" => Some(offset!(+1)),
+                _ => None,
+            }
+        }
+        for (fmt, s, default_offset, expected) in [
+            (
+                "%F %T %z",
+                "2022-03-06 12:34:56 -0130",
+                None,
+                datetime!(2022-03-06 12:34:56 -1:30),
+            ),
+            (
+                "%F %T %Z",
+                "2022-03-06 12:34:56 CET",
+                None,
+                datetime!(2022-03-06 12:34:56 +1),
+            ),
+            (
+                "%F %T %Z",
+                "2022-07-06 12:34:56 CET",
+                None,
+                datetime!(2022-07-06 12:34:56 +2),
+            ),
+            (
+                "%F %T",
+                "2022-03-06 12:34:56",
+                Some(offset!(+9)),
+                datetime!(2022-03-06 12:34:56 +9),
+            ),
+            ("%s", "1646570096", None, datetime!(2022-03-06 12:34:56 UTC)),
+        ] {
+            assert_eq!(
+                parse_offset_date_time(fmt, s, default_offset, resolve)?,
+                expected
+            );
+        }
+        assert_eq!(
+            parse_offset_date_time("%F %T", "2022-03-06 12:34:56", None, resolve),
+            Err(ParseError::MissingOffset)
+        );
+        assert_eq!(
+            parse_offset_date_time(
+                "%F %T %Z",
+                "2022-03-06 12:34:56 XYZ",
+                Some(offset!(UTC)),
+                resolve
+            ),
+            Err(ParseError::UnknownTimeZone("XYZ".to_string()))
+        );
+        assert_eq!(
+            parse_strict_offset_date_time("%F %z", "2022-03-06 Z trailing", None, resolve),
+            Err(ParseError::UnconvertedDataRemains(" trailing".to_string()))
+        );
+
+        // Configurable as `StrptimeFormat` is.
+        let locale = WithCalendar::new(&PosixLocale, &SolarHijri);
+        let options = ParseOptions::new()
+            .reference(datetime!(2022-03-06 00:00 UTC))
+            .check_consistency(true);
+        for (fmt, s, expected) in [
+            (
+                "%Y/%m/%d %T %Z",
+                "1400/12/15 12:34:56 CET",
+                Ok(datetime!(2022-03-06 12:34:56 +1)),
+            ),
+            (
+                "%T %z",
+                "01:23:45 +09:00",
+                Ok(datetime!(2022-03-06 01:23:45 +9)),
+            ),
+            (
+                "%a %T %z",
+                "Mon 01:23:45 +09:00",
+                Err(ParseError::Inconsistent(vec!["day-of-week"])),
+            ),
+        ] {
+            assert_eq!(
+                parse_strict_offset_date_time_with_locale(fmt, s, None, resolve, &locale, &options),
+                expected
+            );
+            let format = StrptimeFormat::new_with_locale(fmt, &locale)?.with_options(options);
+            assert_eq!(
+                format.parse_strict_offset_date_time(s, None, resolve),
+                expected
+            );
+        }
+        assert_eq!(
+            parse_offset_date_time_with_locale(
+                "%Y/%m/%d %X",
+                "1400/12/15 12:34:56 trailing",
+                Some(offset!(+3:30)),
+                resolve,
+                &locale,
+                &ParseOptions::default()
+            )?,
+            datetime!(2022-03-06 12:34:56 +3:30)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_date_time_offset() -> Result<(), ParseError> {
+        for (fmt, s) in [
+            ("%F", "2022-03-06"),
+            ("%Y %j", "2022 065"),
+            ("%y %m %d", "22 03 06"),
+            ("%G-W%V-%u", "2022-W09-7"),
+            ("%Y %U %a", "2022 10 Sun"),
+        ] {
+            assert_eq!(parse_date(fmt, s)?, datetime!(2022-03-06 00:00).date());
+        }
+        for (fmt, s, expected) in [
+            ("%m-%d", "03-06", ParseError::MissingComponent("year")),
+            ("%Y", "2022", ParseError::MissingComponent("month")),
+            (
+                "%Y-%m",
+                "2022-03",
+                ParseError::MissingComponent("day-of-month"),
+            ),
+            (
+                "%F %T",
+                "2022-03-06 12:34:56",
+                ParseError::UnexpectedComponent("hour-of-day"),
+            ),
+            (
+                "%F %z",
+                "2022-03-06 +09:00",
+                ParseError::UnexpectedComponent("offset"),
+            ),
+            (
+                "%s",
+                "1646570096",
+                ParseError::UnexpectedComponent("seconds-since-epoch"),
+            ),
+        ] {
+            assert_eq!(parse_date(fmt, s), Err(expected));
+        }
+        assert_eq!(
+            parse_strict_date("%F", "2022-03-06 trailing"),
+            Err(ParseError::UnconvertedDataRemains(" trailing".to_string()))
+        );
+
+        assert_eq!(parse_time("%T", "12:34:56")?, time!(12:34:56));
+        assert_eq!(parse_time("%I %p", "12 am")?, time!(00:00));
+        assert_eq!(
+            parse_time("%H:%M:%S.%f", "12:34:56.789")?,
+            time!(12:34:56.789)
+        );
+        for (fmt, s, expected) in [
+            (
+                "%M:%S",
+                "34:56",
+                ParseError::MissingComponent("hour-of-day"),
+            ),
+            ("%I:%M", "12:34", ParseError::MissingComponent("am/pm")),
+            (
+                "%T %Z",
+                "12:34:56 JST",
+                ParseError::UnexpectedComponent("timezone-name"),
+            ),
+            (
+                "%F %T",
+                "2022-03-06 12:34:56",
+                ParseError::UnexpectedComponent("year"),
+            ),
+        ] {
+            assert_eq!(parse_time(fmt, s), Err(expected));
+        }
+
+        assert_eq!(parse_offset("%z", "+09:00")?, offset!(+9));
+        assert_eq!(parse_offset("%z", "Z")?, offset!(UTC));
+        assert_eq!(
+            parse_offset("%Z", "JST"),
+            Err(ParseError::UnexpectedComponent("timezone-name"))
+        );
+        assert_eq!(
+            parse_offset("UTC", "UTC"),
+            Err(ParseError::MissingComponent("offset"))
+        );
+        assert_eq!(
+            parse_offset("%H %z", "12 +09:00"),
+            Err(ParseError::UnexpectedComponent("hour-of-day"))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_with_locale() -> Result<(), ParseError> {
+        use super::{
+            parse_date_time_maybe_with_zone_with_locale, parse_date_with_locale,
+            parse_fields_with_locale, parse_offset_with_locale, parse_strict_time_with_locale,
+            ParseOptions,
+        };
+        use crate::{
+            calendar::SolarHijri,
+            locale::{PosixLocale, WithCalendar},
+        };
+
+        let options = ParseOptions::new().check_consistency(true);
+        assert_eq!(
+            parse_date_time_maybe_with_zone_with_locale(
+                "%a %F",
+                "Mon 2022-03-06",
+                &PosixLocale,
+                &options
+            ),
+            Err(ParseError::Inconsistent(vec!["day-of-week"]))
+        );
+        assert_eq!(
+            parse_date_with_locale("%a %F", "Mon 2022-03-06", &PosixLocale, &options),
+            Err(ParseError::Inconsistent(vec!["day-of-week"]))
+        );
+        assert_eq!(
+            parse_date_with_locale("%a %F", "Sun 2022-03-06", &PosixLocale, &options)?,
+            datetime!(2022-03-06 00:00).date()
+        );
+        assert_eq!(
+            parse_strict_time_with_locale("%H %I %p", "13 02 AM", &PosixLocale, &options),
+            Err(ParseError::Inconsistent(vec!["hour-of-half-day", "am/pm"]))
+        );
+        assert_eq!(
+            parse_offset_with_locale("%z", "+09:00", &PosixLocale, &options)?,
+            offset!(+9)
+        );
+
+        let locale = WithCalendar::new(&PosixLocale, &SolarHijri);
+        let options = ParseOptions::default();
+        assert_eq!(
+            parse_date_with_locale("%Y/%m/%d", "1400/12/15", &locale, &options)?,
+            datetime!(2022-03-06 00:00).date()
+        );
+        let parsed = parse_fields_with_locale("%Y/%m/%d", "1400/12/15", &locale, &options)?;
+        assert_eq!(parsed.year, Some(1400));
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_consistency() -> Result<(), ParseError> {
+        use super::{parse_date_time_maybe_with_zone_with_locale, ParseOptions};
+        use crate::locale::PosixLocale;
+
+        let options = ParseOptions::new().check_consistency(true);
+        for (fmt, input, expected) in [
+            ("%a %F", "Sun 2022-03-06", Ok(datetime!(2022-03-06 00:00))),
+            ("%a %F", "Mon 2022-03-06", Err(vec!["day-of-week"])),
+            ("%u %j %F", "1 065 2022-03-06", Err(vec!["day-of-week"])),
+            ("%j %m/%d/%Y", "066 03/06/2022", Err(vec!["day-of-month"])),
+            ("%C %Y", "19 2022", Err(vec!["year-prefix"])),
+            ("%y %Y", "22 2022", Ok(datetime!(2022-01-01 00:00))),
+            ("%H %I %p", "13 01 PM", Ok(datetime!(1900-01-01 13:00))),
+            (
+                "%H %I %p",
+                "13 02 AM",
+                Err(vec!["hour-of-half-day", "am/pm"]),
+            ),
+            ("%F %U %W", "2022-03-06 10 10", Err(vec!["week-number"])),
+            (
+                "%F %G-W%V",
+                "2022-01-01 2022-W01",
+                Err(vec!["iso-week-number", "iso-week-based-year"]),
+            ),
+            ("%s %Y %H", "1646570096 2022 21", Err(vec!["hour-of-day"])),
+        ] {
+            assert!(parse_date_time_maybe_with_zone(fmt, input).is_ok());
+            assert_eq!(
+                parse_date_time_maybe_with_zone_with_locale(fmt, input, &PosixLocale, &options)
+                    .map(|(dt, _)| dt),
+                expected.map_err(ParseError::Inconsistent),
+                "{} {}",
+                fmt,
+                input
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_reference() -> Result<(), ParseError> {
+        use super::{parse_date_time_maybe_with_zone_with_locale, ParseOptions};
+        use crate::locale::PosixLocale;
+
+        let options = ParseOptions::new().reference(datetime!(2022-01-05 12:00 +9));
+        let parse = |fmt, input, options| {
+            parse_date_time_maybe_with_zone_with_locale(fmt, input, &PosixLocale, &options)
+                .map(|(date_time, _)| date_time)
+        };
+        for (fmt, input, expected) in [
+            ("%T", "01:02:03", datetime!(2022-01-05 01:02:03)),
+            ("%T %z", "23:00:00 Z", datetime!(2022-01-05 23:00)),
+            ("%T %z", "02:00:00 -05:00", datetime!(2022-01-04 02:00)),
+            (
+                "%b %e %T",
+                "Dec 31 23:59:59",
+                datetime!(2022-12-31 23:59:59),
+            ),
+            ("%d %H", "20 10", datetime!(2022-01-20 10:00)),
+            ("%M:%S", "34:56", datetime!(2022-01-05 12:34:56)),
+            ("%S", "56", datetime!(2022-01-05 12:00:56)),
+            ("%M %z", "34 -05:00", datetime!(2022-01-04 22:34)),
+            ("%m", "03", datetime!(2022-03-01 00:00)),
+            ("%j", "065", datetime!(2022-03-06 00:00)),
+            ("%Y", "2020", datetime!(2020-01-01 00:00)),
+            ("%F", "2020-02-29", datetime!(2020-02-29 00:00)),
+        ] {
+            assert_eq!(parse(fmt, input, options)?, expected, "{} {}", fmt, input);
+        }
+
+        let options = options.closest_year(true);
+        for (fmt, input, expected) in [
+            (
+                "%b %e %T",
+                "Dec 31 23:59:59",
+                datetime!(2021-12-31 23:59:59),
+            ),
+            ("%b %e %T", "Jan  6 00:00:00", datetime!(2022-01-06 00:00)),
+            ("%b %e", "Jul  6", datetime!(2022-07-06 00:00)),
+            ("%b %e", "Jul  8", datetime!(2021-07-08 00:00)),
+            ("%b %e", "Feb 29", datetime!(2020-02-29 00:00)),
+            ("%T", "01:02:03", datetime!(2022-01-05 01:02:03)),
+        ] {
+            assert_eq!(parse(fmt, input, options)?, expected, "{} {}", fmt, input);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_year_pivot() -> Result<(), ParseError> {
+        use super::{
+            parse_date_time_maybe_with_zone_with_locale, parse_fields_with_locale, ParseOptions,
+            YearPivot,
+        };
+        use crate::locale::PosixLocale;
+
+        let java = YearPivot::Sliding {
+            reference: 2022,
+            before: 80,
+        };
+        for (pivot, fmt, input, expected) in [
+            (
+                YearPivot::default(),
+                "%y",
+                "68",
+                datetime!(2068-01-01 00:00),
+            ),
+            (
+                YearPivot::default(),
+                "%y",
+                "69",
+                datetime!(1969-01-01 00:00),
+            ),
+            (
+                YearPivot::Fixed(1950),
+                "%y",
+                "49",
+                datetime!(2049-01-01 00:00),
+            ),
+            (
+                YearPivot::Fixed(1950),
+                "%y",
+                "50",
+                datetime!(1950-01-01 00:00),
+            ),
+            (
+                YearPivot::Fixed(-50),
+                "%y",
+                "49",
+                datetime!(0049-01-01 00:00),
+            ),
+            (
+                YearPivot::Fixed(-50),
+                "%y",
+                "50",
+                datetime!(-0050-01-01 00:00),
+            ),
+            (java, "%y", "41", datetime!(2041-01-01 00:00)),
+            (java, "%y", "42", datetime!(1942-01-01 00:00)),
+            (java, "%C%y", "2042", datetime!(2042-01-01 00:00)),
+            (java, "%g-W%V-%u", "41-W01-1", datetime!(2040-12-31 00:00)),
+            (java, "%g-W%V-%u", "42-W01-1", datetime!(1941-12-29 00:00)),
+        ] {
+            let options = ParseOptions::new().year_pivot(pivot);
+            let (date_time, _) =
+                parse_date_time_maybe_with_zone_with_locale(fmt, input, &PosixLocale, &options)?;
+            assert_eq!(date_time, expected, "{} {}", fmt, input);
+        }
+        let options = ParseOptions::new().year_pivot(java);
+        let parsed = parse_fields_with_locale("%y", "42", &PosixLocale, &options)?;
+        assert_eq!(parsed.year, Some(1942));
+        Ok(())
+    }
+
     #[test]
     fn test_strict() {
         assert!(matches!(
@@ -826,6 +2660,100 @@ mod tests {
         assert!(parse_date_time_maybe_with_zone("%FT%T %z", "2022-03-06T12:34:56 12:34").is_err());
         assert!(parse_date_time_maybe_with_zone("%FT%T %z", "2022-03-06T12:34:56 +2:34").is_err());
         assert!(parse_date_time_maybe_with_zone("%FT%T %z", "2022-03-06T12:34:56 +234").is_err());
+        assert!(parse_date_time_maybe_with_zone("%FT%T %z", "2022-03-06T12:34:56 +09:").is_err());
+        for (s, offset) in [
+            ("+09", offset!(+9:00)),
+            ("-0930", offset!(-9:30)),
+            ("+09:30", offset!(+9:30)),
+            ("-001859", offset!(-0:18:59)),
+            ("+00:18:59", offset!(+0:18:59)),
+        ] {
+            for fmt in ["%z", "%:z", "%::z", "%:::z"] {
+                assert_eq!(
+                    parse_date_time_maybe_with_zone(fmt, s)?,
+                    (
+                        datetime!(1900-01-01 00:00:00),
+                        Some(TimeZoneSpecifier::Offset(offset))
+                    )
+                );
+            }
+        }
+        assert_eq!(
+            parse_date_time_maybe_with_zone("%z %H", "+09 12")?,
+            (
+                datetime!(1900-01-01 12:00:00),
+                Some(TimeZoneSpecifier::Offset(offset!(+9:00)))
+            )
+        );
+        assert_eq!(
+            parse_date_time_maybe_with_zone("%:Y", "2022"),
+            Err(ParseError::UnknownSpecifier(':'))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_zone_forms() -> Result<(), super::ParseError> {
+        for fmt in ["%z", "%:z", "%::z", "%:::z"] {
+            assert_eq!(
+                parse_strict_date_time_maybe_with_zone(fmt, "Z")?,
+                (
+                    datetime!(1900-01-01 00:00:00),
+                    Some(TimeZoneSpecifier::Offset(offset!(UTC)))
+                )
+            );
+            for (s, offset) in [
+                ("-00", offset!(UTC)),
+                ("+00:00:00", offset!(UTC)),
+                ("+2359", offset!(+23:59)),
+                ("-23:59:59", offset!(-23:59:59)),
+            ] {
+                assert_eq!(
+                    parse_strict_date_time_maybe_with_zone(fmt, s)?,
+                    (
+                        datetime!(1900-01-01 00:00:00),
+                        Some(TimeZoneSpecifier::Offset(offset))
+                    )
+                );
+            }
+            assert_eq!(
+                parse_date_time_maybe_with_zone(fmt, "z"),
+                Err(ParseError::UnexpectedByte("+ or -", b'z'))
+            );
+            assert_eq!(
+                parse_date_time_maybe_with_zone(fmt, ""),
+                Err(ParseError::UnexpectedEnd("+ or -"))
+            );
+            for s in ["+26", "-9900", "+09:60", "-0960", "+09:30:60", "-093099"] {
+                assert!(
+                    matches!(
+                        parse_date_time_maybe_with_zone(fmt, s),
+                        Err(ParseError::ComponentRange(_))
+                    ),
+                    "{} {}",
+                    fmt,
+                    s
+                );
+            }
+            // Colons are all or nothing.
+            for s in ["+0930:00", "+09:3000"] {
+                assert!(matches!(
+                    parse_strict_date_time_maybe_with_zone(fmt, s),
+                    Err(ParseError::UnconvertedDataRemains(_))
+                ));
+            }
+        }
+        assert_eq!(
+            parse_date_time_maybe_with_zone("%FT%T%:z", "2022-03-06T12:34:56Z")?,
+            (
+                datetime!(2022-03-06 12:34:56),
+                Some(TimeZoneSpecifier::Offset(offset!(UTC)))
+            )
+        );
+        assert_eq!(
+            parse_date_time_maybe_with_zone("%::::z", "+09"),
+            Err(ParseError::UnknownSpecifier(':'))
+        );
         Ok(())
     }
 }
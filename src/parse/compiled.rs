@@ -0,0 +1,632 @@
+use std::{fmt, ops::Range};
+
+use time::{OffsetDateTime, PrimitiveDateTime, UtcOffset};
+
+use crate::{
+    locale::{Expanding, Locale, LocaleFormat, PosixLocale},
+    util::Modifier,
+};
+
+use super::{
+    assume_zone,
+    desc_parser::{self, Collector, Spec},
+    subsecond_digits, timestamp_precision, ParseCollector, ParseError, ParseOptions,
+    TimeZoneSpecifier,
+};
+
+/// A conversion specifier, i.e. a required method of `Collector` that consumes a component.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Conversion {
+    DayOfWeekName,
+    MonthName,
+    YearPrefix,
+    DayOfMonth,
+    Iso8601WeekBasedYearSuffix,
+    Iso8601WeekBasedYear,
+    HourOfDay,
+    HourOfDay12,
+    DayOfYear,
+    MonthOfYear,
+    MinuteOfHour,
+    Ampm,
+    SecondsSinceEpoch,
+    SecondOfMinute,
+    NanosecondOfSecond,
+    DayOfWeekFromMondayAs1,
+    WeekNumberOfCurrentYearStartSunday,
+    Iso8601WeekNumber,
+    DayOfWeekFromSundayAs0,
+    WeekNumberOfCurrentYearStartMonday,
+    YearSuffix,
+    Year,
+    Timezone,
+    TimezoneName,
+}
+
+impl Conversion {
+    #[inline]
+    fn apply<C: Collector>(self, collector: &mut C, spec: Spec) -> Result<(), C::Error> {
+        match self {
+            Self::DayOfWeekName => collector.day_of_week_name(spec),
+            Self::MonthName => collector.month_name(spec),
+            Self::YearPrefix => collector.year_prefix(spec),
+            Self::DayOfMonth => collector.day_of_month(spec),
+            Self::Iso8601WeekBasedYearSuffix => collector.iso8601_week_based_year_suffix(spec),
+            Self::Iso8601WeekBasedYear => collector.iso8601_week_based_year(spec),
+            Self::HourOfDay => collector.hour_of_day(spec),
+            Self::HourOfDay12 => collector.hour_of_day_12(spec),
+            Self::DayOfYear => collector.day_of_year(spec),
+            Self::MonthOfYear => collector.month_of_year(spec),
+            Self::MinuteOfHour => collector.minute_of_hour(spec),
+            Self::Ampm => collector.ampm(spec),
+            Self::SecondsSinceEpoch => collector.seconds_since_epoch(spec),
+            Self::SecondOfMinute => collector.second_of_minute(spec),
+            Self::NanosecondOfSecond => collector.nanosecond_of_second(spec),
+            Self::DayOfWeekFromMondayAs1 => collector.day_of_week_from_monday_as_1(spec),
+            Self::WeekNumberOfCurrentYearStartSunday => {
+                collector.week_number_of_current_year_start_sunday(spec)
+            }
+            Self::Iso8601WeekNumber => collector.iso8601_week_number(spec),
+            Self::DayOfWeekFromSundayAs0 => collector.day_of_week_from_sunday_as_0(spec),
+            Self::WeekNumberOfCurrentYearStartMonday => {
+                collector.week_number_of_current_year_start_monday(spec)
+            }
+            Self::YearSuffix => collector.year_suffix(spec),
+            Self::Year => collector.year(spec),
+            Self::Timezone => collector.timezone(spec),
+            Self::TimezoneName => collector.timezone_name(spec),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Item {
+    /// A sequence of whitespaces.
+    Spaces,
+    /// Kept apart from `Literal` so that errors are reported the same way as the uncompiled one.
+    StaticStr(&'static str),
+    /// A range of `StrptimeFormat::literals`.
+    Literal(Range<usize>),
+    Conversion(Conversion, Spec),
+}
+
+/// A `strptime`-like conversion description that is tokenized and validated once, so that it can
+/// parse many inputs without scanning the description again.
+///
+/// The formats of the locale, like that of `%c`, are compiled in place, and the locale is kept to
+/// look up the names, the eras and the alternative digits while parsing.
+///
+/// ```
+/// use time::macros::datetime;
+/// use time_fmt::parse::StrptimeFormat;
+///
+/// let format = StrptimeFormat::new("%Y-%m-%d %H:%M:%S").unwrap();
+/// assert_eq!(
+///     format.parse("2022-03-06 12:34:56").unwrap(),
+///     (datetime!(2022-03-06 12:34:56), None)
+/// );
+/// ```
+#[derive(Clone)]
+pub struct StrptimeFormat<'l> {
+    literals: String,
+    items: Vec<Item>,
+    options: ParseOptions,
+    locale: &'l dyn Locale,
+}
+
+impl fmt::Debug for StrptimeFormat<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StrptimeFormat")
+            .field("literals", &self.literals)
+            .field("items", &self.items)
+            .field("options", &self.options)
+            .finish_non_exhaustive()
+    }
+}
+
+struct CompileCollector<'l> {
+    literals: String,
+    items: Vec<Item>,
+    locale: &'l dyn Locale,
+    /// Locale-provided formats being expanded.
+    expanding: Expanding,
+}
+
+impl CompileCollector<'_> {
+    /// Compiles the locale-provided `format` in place, as `ParseCollector` expands it.
+    fn expand(&mut self, format: LocaleFormat) -> Result<(), ParseError> {
+        let expanding = self
+            .expanding
+            .enter(format)
+            .ok_or(ParseError::RecursiveLocaleFormat)?;
+        let saved = self.expanding;
+        self.expanding = expanding;
+        let result = desc_parser::collect(format.get(self.locale), self);
+        self.expanding = saved;
+        result
+    }
+
+    #[inline]
+    fn push(&mut self, conversion: Conversion, spec: Spec) -> Result<(), ParseError> {
+        self.items.push(Item::Conversion(conversion, spec));
+        Ok(())
+    }
+
+    #[inline]
+    fn push_literal(&mut self, lit: &str) -> Result<(), ParseError> {
+        let start = self.literals.len();
+        self.literals.push_str(lit);
+        let end = self.literals.len();
+        // Merge with the previous literal, which is always adjacent in `literals`.
+        if let Some(Item::Literal(range)) = self.items.last_mut() {
+            range.end = end;
+        } else {
+            self.items.push(Item::Literal(start..end));
+        }
+        Ok(())
+    }
+}
+
+impl<'l> Collector for CompileCollector<'l> {
+    type Output = StrptimeFormat<'l>;
+    type Error = ParseError;
+
+    #[inline]
+    fn spaces(&mut self) -> Result<(), Self::Error> {
+        // A sequence of whitespaces followed by another one is the same as a single one.
+        if self.items.last() != Some(&Item::Spaces) {
+            self.items.push(Item::Spaces);
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn day_of_week_name(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::DayOfWeekName, spec)
+    }
+
+    #[inline]
+    fn month_name(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::MonthName, spec)
+    }
+
+    #[inline]
+    fn preferred_date_time(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.expand(LocaleFormat::date_time(
+            spec.modifier == Some(Modifier::Era),
+        ))
+    }
+
+    #[inline]
+    fn year_prefix(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::YearPrefix, spec)
+    }
+
+    #[inline]
+    fn day_of_month(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::DayOfMonth, spec)
+    }
+
+    #[inline]
+    fn hour_of_day(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::HourOfDay, spec)
+    }
+
+    #[inline]
+    fn hour_of_day_12(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::HourOfDay12, spec)
+    }
+
+    #[inline]
+    fn iso8601_week_based_year_suffix(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::Iso8601WeekBasedYearSuffix, spec)
+    }
+
+    #[inline]
+    fn iso8601_week_based_year(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::Iso8601WeekBasedYear, spec)
+    }
+
+    #[inline]
+    fn day_of_year(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::DayOfYear, spec)
+    }
+
+    #[inline]
+    fn month_of_year(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::MonthOfYear, spec)
+    }
+
+    #[inline]
+    fn minute_of_hour(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::MinuteOfHour, spec)
+    }
+
+    #[inline]
+    fn ampm(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::Ampm, spec)
+    }
+
+    #[inline]
+    fn time_ampm(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        if self.locale.t_fmt_ampm() == PosixLocale.t_fmt_ampm() {
+            desc_parser::posix_time_ampm(self, spec)
+        } else {
+            self.expand(LocaleFormat::TimeAmPm)
+        }
+    }
+
+    #[inline]
+    fn seconds_since_epoch(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        timestamp_precision(spec)?;
+        self.push(Conversion::SecondsSinceEpoch, spec)
+    }
+
+    #[inline]
+    fn second_of_minute(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::SecondOfMinute, spec)
+    }
+
+    #[inline]
+    fn nanosecond_of_second(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        subsecond_digits(spec)?;
+        self.push(Conversion::NanosecondOfSecond, spec)
+    }
+
+    #[inline]
+    fn day_of_week_from_monday_as_1(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::DayOfWeekFromMondayAs1, spec)
+    }
+
+    #[inline]
+    fn week_number_of_current_year_start_sunday(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::WeekNumberOfCurrentYearStartSunday, spec)
+    }
+
+    #[inline]
+    fn iso8601_week_number(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::Iso8601WeekNumber, spec)
+    }
+
+    #[inline]
+    fn day_of_week_from_sunday_as_0(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::DayOfWeekFromSundayAs0, spec)
+    }
+
+    #[inline]
+    fn week_number_of_current_year_start_monday(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::WeekNumberOfCurrentYearStartMonday, spec)
+    }
+
+    #[inline]
+    fn preferred_date(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.expand(LocaleFormat::date(spec.modifier == Some(Modifier::Era)))
+    }
+
+    #[inline]
+    fn preferred_time_of_day(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.expand(LocaleFormat::time(spec.modifier == Some(Modifier::Era)))
+    }
+
+    #[inline]
+    fn year_suffix(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::YearSuffix, spec)
+    }
+
+    #[inline]
+    fn year(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::Year, spec)
+    }
+
+    #[inline]
+    fn timezone(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::Timezone, spec)
+    }
+
+    #[inline]
+    fn timezone_name(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        self.push(Conversion::TimezoneName, spec)
+    }
+
+    #[inline]
+    fn static_str(&mut self, s: &'static str) -> Result<(), Self::Error> {
+        self.items.push(Item::StaticStr(s));
+        Ok(())
+    }
+
+    #[inline]
+    fn literal(
+        &mut self,
+        lit: &str,
+        _fmt_span: impl std::slice::SliceIndex<[u8], Output = [u8]>,
+    ) -> Result<(), Self::Error> {
+        self.push_literal(lit)
+    }
+
+    #[inline]
+    fn unknown(&mut self, specifier: char) -> Result<(), Self::Error> {
+        Err(Self::Error::UnknownSpecifier(specifier))
+    }
+
+    #[inline]
+    fn unconsumed_input(&self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    #[inline]
+    fn output(self) -> Result<Self::Output, Self::Error> {
+        Ok(StrptimeFormat {
+            literals: self.literals,
+            items: self.items,
+            options: ParseOptions::default(),
+            locale: self.locale,
+        })
+    }
+}
+
+impl StrptimeFormat<'static> {
+    /// Tokenizes and validates the conversion description, in the C/POSIX locale.
+    pub fn new(fmt: &str) -> Result<Self, ParseError> {
+        Self::new_with_locale(fmt, &PosixLocale)
+    }
+}
+
+impl<'l> StrptimeFormat<'l> {
+    /// Tokenizes and validates the conversion description, in `locale`.
+    pub fn new_with_locale(fmt: &str, locale: &'l dyn Locale) -> Result<Self, ParseError> {
+        let collector = CompileCollector {
+            literals: String::new(),
+            items: Vec::new(),
+            locale,
+            expanding: Expanding::default(),
+        };
+        desc_parser::parse_format_specifications(fmt, collector, false)
+    }
+
+    /// Replaces the options used to parse inputs.
+    pub fn with_options(self, options: ParseOptions) -> Self {
+        Self { options, ..self }
+    }
+
+    /// Feeds the compiled description to `collector` as if it were parsed right now.
+    fn replay<C: Collector>(&self, mut collector: C, strict: bool) -> Result<C::Output, C::Error> {
+        for item in &self.items {
+            match item {
+                Item::Spaces => collector.spaces()?,
+                Item::StaticStr(s) => collector.static_str(s)?,
+                Item::Literal(range) => {
+                    collector.literal(&self.literals[range.clone()], range.clone())?
+                }
+                Item::Conversion(conversion, spec) => conversion.apply(&mut collector, *spec)?,
+            }
+        }
+        if strict {
+            collector.unconsumed_input()?;
+        }
+        collector.output()
+    }
+
+    pub fn parse<'a>(
+        &self,
+        s: &'a str,
+    ) -> Result<(PrimitiveDateTime, Option<TimeZoneSpecifier<'a>>), ParseError> {
+        self.replay(
+            ParseCollector::new(s)
+                .with_locale(self.locale)
+                .with_options(self.options),
+            false,
+        )
+    }
+
+    pub fn parse_strict<'a>(
+        &self,
+        s: &'a str,
+    ) -> Result<(PrimitiveDateTime, Option<TimeZoneSpecifier<'a>>), ParseError> {
+        self.replay(
+            ParseCollector::new(s)
+                .with_locale(self.locale)
+                .with_options(self.options),
+            true,
+        )
+    }
+
+    /// Parses into an `OffsetDateTime`, as [`super::parse_offset_date_time`] does.
+    pub fn parse_offset_date_time(
+        &self,
+        s: &str,
+        default_offset: Option<UtcOffset>,
+        resolve: impl FnOnce(&str, PrimitiveDateTime) -> Option<UtcOffset>,
+    ) -> Result<OffsetDateTime, ParseError> {
+        let (date_time, zone) = self.parse(s)?;
+        assume_zone(date_time, zone, default_offset, resolve)
+    }
+
+    /// Parses into an `OffsetDateTime`, as [`super::parse_strict_offset_date_time`] does.
+    pub fn parse_strict_offset_date_time(
+        &self,
+        s: &str,
+        default_offset: Option<UtcOffset>,
+        resolve: impl FnOnce(&str, PrimitiveDateTime) -> Option<UtcOffset>,
+    ) -> Result<OffsetDateTime, ParseError> {
+        let (date_time, zone) = self.parse_strict(s)?;
+        assume_zone(date_time, zone, default_offset, resolve)
+    }
+}
+
+impl std::str::FromStr for StrptimeFormat<'static> {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::{datetime, offset};
+
+    use super::{
+        super::{
+            parse_date_time_maybe_with_zone, parse_date_time_maybe_with_zone_with_locale,
+            parse_strict_date_time_maybe_with_zone, YearPivot,
+        },
+        ParseError, ParseOptions, PosixLocale, StrptimeFormat, TimeZoneSpecifier,
+    };
+
+    #[test]
+    fn test_same_as_uncompiled() -> Result<(), ParseError> {
+        for (fmt, input) in [
+            ("%c", "Sun Mar  6 12:34:56 2022"),
+            ("%a %b %d %H:%M:%S %Y", "sunday   march 6 2:34:5 2022"),
+            ("%D %r", "3 / 6 / 22 12 : 34 : 56 PM"),
+            ("%F %T.%f %z", "2022-03-06 12:34:56.789 +0900"),
+            ("%C%y %j %I %p", "2022 65 1 am"),
+            ("%U %w %W %n%t%%", "10 0 09 \t %"),
+            ("%FT%T%Z", "2022-03-06T12:34:56JST"),
+            ("%s.%f %z", "1646570096.789 +0900"),
+            ("%T %::z", "12:34:56 -00:18:59"),
+        ] {
+            let format = StrptimeFormat::new(fmt)?;
+            assert_eq!(
+                format.parse(input)?,
+                parse_date_time_maybe_with_zone(fmt, input)?
+            );
+            assert_eq!(
+                format.parse_strict(input)?,
+                parse_strict_date_time_maybe_with_zone(fmt, input)?
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_reusable() -> Result<(), ParseError> {
+        let format: StrptimeFormat = "%F %T %z".parse()?;
+        assert_eq!(
+            format.parse("2022-03-06 12:34:56 -0130")?,
+            (
+                datetime!(2022-03-06 12:34:56),
+                Some(TimeZoneSpecifier::Offset(offset!(-1:30)))
+            )
+        );
+        assert_eq!(
+            format.parse("1999-12-31   23:59:59   Z trailing")?,
+            (
+                datetime!(1999-12-31 23:59:59),
+                Some(TimeZoneSpecifier::Offset(offset!(UTC)))
+            )
+        );
+        assert!(matches!(
+            format.parse_strict("1999-12-31 23:59:59 Z trailing"),
+            Err(ParseError::UnconvertedDataRemains(_))
+        ));
+        assert_eq!(
+            format.parse("1999/12/31 23:59:59 Z"),
+            Err(ParseError::NotMatch("-"))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_consistency() -> Result<(), ParseError> {
+        let options = ParseOptions::new().check_consistency(true);
+        for (fmt, input) in [
+            ("%a %F", "Sun 2022-03-06"),
+            ("%a %F", "Mon 2022-03-06"),
+            ("%j %m/%d/%Y", "066 03/06/2022"),
+            ("%H %I %p", "13 02 AM"),
+            ("%s %Y %H", "1646570096 2022 21"),
+        ] {
+            let format = StrptimeFormat::new(fmt)?.with_options(options);
+            assert_eq!(
+                format.parse(input),
+                parse_date_time_maybe_with_zone_with_locale(fmt, input, &PosixLocale, &options),
+                "{} {}",
+                fmt,
+                input
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_reference() -> Result<(), ParseError> {
+        let options = ParseOptions::new().reference(datetime!(2022-01-05 12:00 +9));
+        for options in [options, options.closest_year(true)] {
+            for (fmt, input) in [
+                ("%T", "01:02:03"),
+                ("%T %z", "02:00:00 -05:00"),
+                ("%b %e %T", "Dec 31 23:59:59"),
+                ("%b %e", "Feb 29"),
+                ("%d %H", "20 10"),
+                ("%M:%S", "34:56"),
+                ("%j", "065"),
+                ("%F", "2020-02-29"),
+            ] {
+                let format = StrptimeFormat::new(fmt)?.with_options(options);
+                assert_eq!(
+                    format.parse(input),
+                    parse_date_time_maybe_with_zone_with_locale(fmt, input, &PosixLocale, &options),
+                    "{} {}",
+                    fmt,
+                    input
+                );
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_year_pivot() -> Result<(), ParseError> {
+        let java = YearPivot::Sliding {
+            reference: 2022,
+            before: 80,
+        };
+        for (pivot, fmt, input) in [
+            (YearPivot::default(), "%y", "69"),
+            (YearPivot::Fixed(1950), "%y", "49"),
+            (YearPivot::Fixed(-50), "%y", "50"),
+            (java, "%y", "42"),
+            (java, "%C%y", "2042"),
+            (java, "%g-W%V-%u", "42-W01-1"),
+        ] {
+            let options = ParseOptions::new().year_pivot(pivot);
+            let format = StrptimeFormat::new(fmt)?.with_options(options);
+            assert_eq!(
+                format.parse(input),
+                parse_date_time_maybe_with_zone_with_locale(fmt, input, &PosixLocale, &options),
+                "{} {}",
+                fmt,
+                input
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_offset_date_time() -> Result<(), ParseError> {
+        let format = StrptimeFormat::new("%F %T %Z")?;
+        let resolve = |name: &str, _| (name == "JST").then_some(offset!(+9));
+        assert_eq!(
+            format.parse_offset_date_time("2022-03-06 12:34:56 JST", None, resolve)?,
+            datetime!(2022-03-06 12:34:56 +9)
+        );
+        assert_eq!(
+            format.parse_strict_offset_date_time("2022-03-06 12:34:56 EST", None, resolve),
+            Err(ParseError::UnknownTimeZone("EST".to_string()))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown_specifier() {
+        assert_eq!(
+            StrptimeFormat::new("%Y %q").unwrap_err(),
+            ParseError::UnknownSpecifier('q')
+        );
+        assert_eq!(
+            StrptimeFormat::new("%.10s").unwrap_err(),
+            ParseError::UnsupportedPrecision('s', 10)
+        );
+    }
+}
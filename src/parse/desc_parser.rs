@@ -1,102 +1,129 @@
 use std::slice::SliceIndex;
 
-/// E and O are not implemented.
+use crate::util::Modifier;
+
+/// What comes between `%` and the conversion specifier.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Spec {
+    /// Field width.
+    pub(crate) width: Option<usize>,
+    /// `.N` after the width, only allowed before `s`.
+    pub(crate) precision: Option<usize>,
+    /// The number of `:`s, only allowed up to 3 before `z`.
+    pub(crate) colons: u8,
+    /// `E` or `O`.
+    pub(crate) modifier: Option<Modifier>,
+}
+
+impl Spec {
+    /// What to pass to the components of a composite specifier like `%c`.
+    /// The width, the precision, colons and the modifier are not passed down.
+    #[inline]
+    pub(crate) fn inherited(self) -> Self {
+        Self::default()
+    }
+}
+
+/// E and O modifiers are passed as `spec.modifier` to the same method as the unmodified one; it's
+/// fine to ignore them as that's what POSIX locale does.
 /// Those require `nl-langinfo` lookup is default-implemented as if it were a POSIX locale.
 /// If you'd want to implement it properly, it's your responsibility to recursively parse
-/// the format you get from `nl-langinfo`, and prevent infinite recursion.
+/// the format you get from `nl-langinfo` with `collect`, and prevent infinite recursion.
 pub(crate) trait Collector {
     type Output;
     type Error;
     /// Skips sequence of whitespaces.
     fn spaces(&mut self) -> Result<(), Self::Error>;
     /// `%a` or `%A`. `nl_langinfo`-dependent.
-    fn day_of_week_name(&mut self) -> Result<(), Self::Error>;
+    fn day_of_week_name(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%b`, `%B` or `%h`. `nl_langinfo`-dependent.
-    fn month_name(&mut self) -> Result<(), Self::Error>;
+    fn month_name(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%c`. Same as `%a %b %e %T %Y` in POSIX locale. `nl_langinfo`-dependent.
     #[inline]
-    fn preferred_date_time(&mut self) -> Result<(), Self::Error> {
-        self.day_of_week_name()?;
+    fn preferred_date_time(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let spec = spec.inherited();
+        self.day_of_week_name(spec)?;
         self.spaces()?;
-        self.month_name()?;
+        self.month_name(spec)?;
         self.spaces()?;
-        self.day_of_month()?;
+        self.day_of_month(spec)?;
         self.spaces()?;
-        self.time_of_day()?;
+        self.time_of_day(spec)?;
         self.spaces()?;
-        self.year()
+        self.year(spec)
     }
     /// `%C`. `0` to `99`.
-    fn year_prefix(&mut self) -> Result<(), Self::Error>;
+    fn year_prefix(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%d`, `%e`. `01` to `31`.
-    fn day_of_month(&mut self) -> Result<(), Self::Error>;
+    fn day_of_month(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%D`. `%m / %d / %y` (American......).
     #[inline]
-    fn date_mmddyy_slash(&mut self) -> Result<(), Self::Error> {
-        self.month_of_year()?;
+    fn date_mmddyy_slash(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let spec = spec.inherited();
+        self.month_of_year(spec)?;
         self.spaces()?;
         self.static_str("/")?;
         self.spaces()?;
-        self.day_of_month()?;
+        self.day_of_month(spec)?;
         self.spaces()?;
         self.static_str("/")?;
         self.spaces()?;
-        self.year_suffix()
+        self.year_suffix(spec)
     }
     /// `%F`. `%Y-%m-%d`.
     #[inline]
-    fn date_yyyymmdd_hyphen(&mut self) -> Result<(), Self::Error> {
-        self.year()?;
+    fn date_yyyymmdd_hyphen(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let spec = spec.inherited();
+        self.year(spec)?;
         self.static_str("-")?;
-        self.month_of_year()?;
+        self.month_of_year(spec)?;
         self.static_str("-")?;
-        self.day_of_month()
+        self.day_of_month(spec)
     }
+    /// `%g`. The last 2 digits of the ISO 8601 week-based year, `00` to `99`.
+    fn iso8601_week_based_year_suffix(&mut self, spec: Spec) -> Result<(), Self::Error>;
+    /// `%G`. ISO 8601 week-based year.
+    fn iso8601_week_based_year(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%H`, `%k`. `00` to `23`.
-    fn hour_of_day(&mut self) -> Result<(), Self::Error>;
+    fn hour_of_day(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%I`, `%l`. `01` to `12`.
-    fn hour_of_day_12(&mut self) -> Result<(), Self::Error>;
+    fn hour_of_day_12(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%j`. `001` to `336`.
-    fn day_of_year(&mut self) -> Result<(), Self::Error>;
+    fn day_of_year(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%m`. `01` to `12`.
-    fn month_of_year(&mut self) -> Result<(), Self::Error>;
+    fn month_of_year(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%M`. `00` to `59`.
-    fn minute_of_hour(&mut self) -> Result<(), Self::Error>;
+    fn minute_of_hour(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%n`.
     #[inline]
     fn new_line(&mut self) -> Result<(), Self::Error> {
         self.spaces()
     }
     /// `%p`, `%P`. `AM` or `PM`. `nl_langinfo`-dependent.
-    fn ampm(&mut self) -> Result<(), Self::Error>;
+    fn ampm(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%r`. Same as `%I : %M : %S %p` in POSIX locale. `nl_langinfo`-dependent.
     #[inline]
-    fn time_ampm(&mut self) -> Result<(), Self::Error> {
-        self.hour_of_day_12()?;
-        self.spaces()?;
-        self.static_str(":")?;
-        self.spaces()?;
-        self.minute_of_hour()?;
-        self.spaces()?;
-        self.static_str(":")?;
-        self.spaces()?;
-        self.second_of_minute()?;
-        self.spaces()?;
-        self.ampm()
+    fn time_ampm(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        posix_time_ampm(self, spec)
     }
     /// `%R`. Same as `%H : %M`.
     #[inline]
-    fn hour_minute_of_day(&mut self) -> Result<(), Self::Error> {
-        self.hour_of_day()?;
+    fn hour_minute_of_day(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let spec = spec.inherited();
+        self.hour_of_day(spec)?;
         self.spaces()?;
         self.static_str(":")?;
         self.spaces()?;
-        self.minute_of_hour()
+        self.minute_of_hour(spec)
     }
+    /// `%s`. Seconds since the Unix epoch, possibly signed. With a precision `.N` (up to 9), in
+    /// units of `10^-N` seconds instead, e.g. `%.3s` for milliseconds.
+    fn seconds_since_epoch(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%S`. `00` to `60`.
-    fn second_of_minute(&mut self) -> Result<(), Self::Error>;
-    /// `%f`. `000000000` to `999999999`.
-    fn nanosecond_of_second(&mut self) -> Result<(), Self::Error>;
+    fn second_of_minute(&mut self, spec: Spec) -> Result<(), Self::Error>;
+    /// `%f` and `%N`. Up to 9 digits, or up to `N` digits with a field width `N`. Digits beyond that
+    /// are consumed but truncated.
+    fn nanosecond_of_second(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%t`.
     #[inline]
     fn tab(&mut self) -> Result<(), Self::Error> {
@@ -104,49 +131,57 @@ pub(crate) trait Collector {
     }
     /// `%T`. Same as `%H : %M : %S`.
     #[inline]
-    fn time_of_day(&mut self) -> Result<(), Self::Error> {
-        self.hour_of_day()?;
+    fn time_of_day(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let spec = spec.inherited();
+        self.hour_of_day(spec)?;
         self.spaces()?;
         self.static_str(":")?;
         self.spaces()?;
-        self.minute_of_hour()?;
+        self.minute_of_hour(spec)?;
         self.spaces()?;
         self.static_str(":")?;
         self.spaces()?;
-        self.second_of_minute()
+        self.second_of_minute(spec)
     }
+    /// `%u`. `1` (Monday) to `7` (Sunday).
+    fn day_of_week_from_monday_as_1(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%U`. `00` to `53`.
-    fn week_number_of_current_year_start_sunday(&mut self) -> Result<(), Self::Error>;
+    fn week_number_of_current_year_start_sunday(&mut self, spec: Spec) -> Result<(), Self::Error>;
+    /// `%V`. ISO 8601 week number, `01` to `53`.
+    fn iso8601_week_number(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%w`.
-    fn day_of_week_from_sunday_as_0(&mut self) -> Result<(), Self::Error>;
+    fn day_of_week_from_sunday_as_0(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%W`. `00` to `53`.
-    fn week_number_of_current_year_start_monday(&mut self) -> Result<(), Self::Error>;
+    fn week_number_of_current_year_start_monday(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%x`. `%m/%d/%y` in POSIX locale. `nl_langinfo`-dependent.
     #[inline]
-    fn preferred_date(&mut self) -> Result<(), Self::Error> {
-        self.month_of_year()?;
+    fn preferred_date(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let spec = spec.inherited();
+        self.month_of_year(spec)?;
         self.static_str("/")?;
-        self.day_of_month()?;
+        self.day_of_month(spec)?;
         self.static_str("/")?;
-        self.year_suffix()
+        self.year_suffix(spec)
     }
     /// `%X`. `%H:%M:%S` in POSIX locale. `nl_langinfo`-dependent.
     #[inline]
-    fn preferred_time_of_day(&mut self) -> Result<(), Self::Error> {
-        self.hour_of_day()?;
+    fn preferred_time_of_day(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let spec = spec.inherited();
+        self.hour_of_day(spec)?;
         self.static_str(":")?;
-        self.minute_of_hour()?;
+        self.minute_of_hour(spec)?;
         self.static_str(":")?;
-        self.second_of_minute()
+        self.second_of_minute(spec)
     }
     /// `%y`. `00` to `99`.
-    fn year_suffix(&mut self) -> Result<(), Self::Error>;
+    fn year_suffix(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%Y`.
-    fn year(&mut self) -> Result<(), Self::Error>;
-    /// `%z`. `+hhmm` or `-hhmm`.
-    fn timezone(&mut self) -> Result<(), Self::Error>;
+    fn year(&mut self, spec: Spec) -> Result<(), Self::Error>;
+    /// `%z`, `%:z`, `%::z` and `%:::z`. `Z`, `+hh`, `+hhmm`, `+hh:mm`, `+hhmmss` or `+hh:mm:ss`,
+    /// or with `-`, regardless of the number of colons.
+    fn timezone(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%Z`. Timezone name or abbreviation.
-    fn timezone_name(&mut self) -> Result<(), Self::Error>;
+    fn timezone_name(&mut self, spec: Spec) -> Result<(), Self::Error>;
     /// `%%`.
     #[inline]
     fn percent(&mut self) -> Result<(), Self::Error> {
@@ -167,18 +202,47 @@ pub(crate) trait Collector {
     /// `%(something else)`.
     fn unknown(&mut self, specifier: char) -> Result<(), Self::Error>;
 
-    /// Check for remaining unconsumed input.
+    /// Check for remaining unconsumed input, or anything else the strict mode rejects.
     fn unconsumed_input(&self) -> Result<(), Self::Error>;
 
     /// Construct the final result from what you've collected.
     fn output(self) -> Result<Self::Output, Self::Error>;
 }
 
+/// `%r` of POSIX locale, `%I : %M : %S %p`, for collectors overriding `time_ampm` to fall back to.
+pub(crate) fn posix_time_ampm<C: Collector + ?Sized>(
+    collector: &mut C,
+    spec: Spec,
+) -> Result<(), C::Error> {
+    let spec = spec.inherited();
+    collector.hour_of_day_12(spec)?;
+    collector.spaces()?;
+    collector.static_str(":")?;
+    collector.spaces()?;
+    collector.minute_of_hour(spec)?;
+    collector.spaces()?;
+    collector.static_str(":")?;
+    collector.spaces()?;
+    collector.second_of_minute(spec)?;
+    collector.spaces()?;
+    collector.ampm(spec)
+}
+
 pub(crate) fn parse_format_specifications<C: Collector>(
-    mut format: &str,
+    format: &str,
     mut collector: C,
     strict: bool,
 ) -> Result<C::Output, C::Error> {
+    collect(format, &mut collector)?;
+    if strict {
+        collector.unconsumed_input()?;
+    };
+    collector.output()
+}
+
+/// Feeds `format` to `collector` without finishing it, so that a collector can expand a format in
+/// the middle of another one.
+pub(crate) fn collect<C: Collector>(mut format: &str, collector: &mut C) -> Result<(), C::Error> {
     let original_len = format.len();
     while !format.is_empty() {
         let i = format
@@ -200,37 +264,52 @@ pub(crate) fn parse_format_specifications<C: Collector>(
         }
         assert_eq!(format.as_bytes()[0], b'%');
         format = &format[1..];
-        if let Some(b) = format.bytes().next() {
+        let (spec, rest) = parse_spec(format);
+        if let Some(b) = rest.bytes().next() {
+            format = rest;
+            if spec.colons > 0 && (b != b'z' || spec.colons > 3) {
+                collector.unknown(':')?;
+                continue;
+            }
+            if spec.precision.is_some() && b != b's' {
+                collector.unknown('.')?;
+                continue;
+            }
             match b {
-                b'a' | b'A' => collector.day_of_week_name()?,
-                b'b' | b'B' | b'h' => collector.month_name()?,
-                b'c' => collector.preferred_date_time()?,
-                b'C' => collector.year_prefix()?,
-                b'd' | b'e' => collector.day_of_month()?,
-                b'D' => collector.date_mmddyy_slash()?,
-                b'F' => collector.date_yyyymmdd_hyphen()?,
-                b'H' | b'k' => collector.hour_of_day()?,
-                b'I' | b'l' => collector.hour_of_day_12()?,
-                b'j' => collector.day_of_year()?,
-                b'm' => collector.month_of_year()?,
-                b'M' => collector.minute_of_hour()?,
+                b'a' | b'A' => collector.day_of_week_name(spec)?,
+                b'b' | b'B' | b'h' => collector.month_name(spec)?,
+                b'c' => collector.preferred_date_time(spec)?,
+                b'C' => collector.year_prefix(spec)?,
+                b'd' | b'e' => collector.day_of_month(spec)?,
+                b'D' => collector.date_mmddyy_slash(spec)?,
+                b'F' => collector.date_yyyymmdd_hyphen(spec)?,
+                b'g' => collector.iso8601_week_based_year_suffix(spec)?,
+                b'G' => collector.iso8601_week_based_year(spec)?,
+                b'H' | b'k' => collector.hour_of_day(spec)?,
+                b'I' | b'l' => collector.hour_of_day_12(spec)?,
+                b'j' => collector.day_of_year(spec)?,
+                b'm' => collector.month_of_year(spec)?,
+                b'M' => collector.minute_of_hour(spec)?,
                 b'n' => collector.new_line()?,
-                b'p' | b'P' => collector.ampm()?,
-                b'r' => collector.time_ampm()?,
-                b'R' => collector.hour_minute_of_day()?,
-                b'S' => collector.second_of_minute()?,
-                b'f' => collector.nanosecond_of_second()?,
+                b'p' | b'P' => collector.ampm(spec)?,
+                b'r' => collector.time_ampm(spec)?,
+                b'R' => collector.hour_minute_of_day(spec)?,
+                b's' => collector.seconds_since_epoch(spec)?,
+                b'S' => collector.second_of_minute(spec)?,
+                b'f' | b'N' => collector.nanosecond_of_second(spec)?,
                 b't' => collector.tab()?,
-                b'T' => collector.time_of_day()?,
-                b'U' => collector.week_number_of_current_year_start_sunday()?,
-                b'w' => collector.day_of_week_from_sunday_as_0()?,
-                b'W' => collector.week_number_of_current_year_start_monday()?,
-                b'x' => collector.preferred_date()?,
-                b'X' => collector.preferred_time_of_day()?,
-                b'y' => collector.year_suffix()?,
-                b'Y' => collector.year()?,
-                b'z' => collector.timezone()?,
-                b'Z' => collector.timezone_name()?,
+                b'T' => collector.time_of_day(spec)?,
+                b'u' => collector.day_of_week_from_monday_as_1(spec)?,
+                b'U' => collector.week_number_of_current_year_start_sunday(spec)?,
+                b'V' => collector.iso8601_week_number(spec)?,
+                b'w' => collector.day_of_week_from_sunday_as_0(spec)?,
+                b'W' => collector.week_number_of_current_year_start_monday(spec)?,
+                b'x' => collector.preferred_date(spec)?,
+                b'X' => collector.preferred_time_of_day(spec)?,
+                b'y' => collector.year_suffix(spec)?,
+                b'Y' => collector.year(spec)?,
+                b'z' => collector.timezone(spec)?,
+                b'Z' => collector.timezone_name(spec)?,
                 b'%' => collector.percent()?,
                 _ => {
                     let c = format.chars().next().unwrap();
@@ -244,10 +323,42 @@ pub(crate) fn parse_format_specifications<C: Collector>(
             collector.percent()?;
         }
     }
+    Ok(())
+}
 
-    if strict {
-        collector.unconsumed_input()?;
-    };
-
-    collector.output()
+/// Parses the field width, the precision, colons and the modifier, and returns them with the rest
+/// of the format.
+fn parse_spec(format: &str) -> (Spec, &str) {
+    let mut spec = Spec::default();
+    let bytes = format.as_bytes();
+    let mut i = 0;
+    while let Some(&b) = bytes.get(i) {
+        if !b.is_ascii_digit() {
+            break;
+        }
+        let width = spec.width.unwrap_or(0);
+        spec.width = Some(width.saturating_mul(10).saturating_add((b - b'0') as usize));
+        i += 1;
+    }
+    if bytes.get(i) == Some(&b'.') {
+        i += 1;
+        let mut precision = 0usize;
+        while let Some(&b) = bytes.get(i).filter(|b| b.is_ascii_digit()) {
+            precision = precision
+                .saturating_mul(10)
+                .saturating_add((b - b'0') as usize);
+            i += 1;
+        }
+        spec.precision = Some(precision);
+    }
+    while bytes.get(i) == Some(&b':') {
+        spec.colons = spec.colons.saturating_add(1);
+        i += 1;
+    }
+    match bytes.get(i) {
+        Some(b'E') => spec.modifier = Some(Modifier::Era),
+        Some(b'O') => spec.modifier = Some(Modifier::AlternativeDigits),
+        _ => return (spec, &format[i..]),
+    }
+    (spec, &format[(i + 1)..])
 }
@@ -0,0 +1,352 @@
+use core::fmt;
+
+use time::{Date, Month, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset, Weekday};
+
+use super::{in_gregorian_year, timestamp_nanos, week_date, ParseError, TimeZoneSpecifier};
+use crate::calendar::{Calendar, Gregorian};
+
+/// The fields parsed, each of which is `None` unless given, instead of defaulting to 1900-01-01
+/// 00:00:00 as the `parse_*` functions do.
+///
+/// The conversion methods fail with [`ParseError::MissingComponent`] if a field they need isn't
+/// given, except that the minute, the second and the subsecond default to 0. Fill the fields
+/// beforehand to default them in other ways.
+///
+/// The year, the month, the day and the day of the year are in the calendar of the locale parsed
+/// with, which is kept to convert them, and the Gregorian one by default.
+#[derive(Clone, Copy)]
+#[non_exhaustive]
+pub struct Parsed<'a> {
+    pub(super) calendar: &'a dyn Calendar,
+    /// The year, by `%Y`, `%C` and `%y`, or the era.
+    pub year: Option<i32>,
+    /// `%C`.
+    pub century: Option<i32>,
+    /// `%y`.
+    pub year_of_century: Option<u8>,
+    /// `%m` or `%b`.
+    pub month: Option<Month>,
+    /// `%d` or `%e`.
+    pub day: Option<u8>,
+    /// `%j`.
+    pub ordinal: Option<u16>,
+    /// The ISO 8601 week-based year, by `%G` or `%g`.
+    pub iso_year: Option<i32>,
+    /// `%g`.
+    pub iso_year_of_century: Option<u8>,
+    /// `%V`.
+    pub iso_week: Option<u8>,
+    /// `%U`.
+    pub sunday_week: Option<u8>,
+    /// `%W`.
+    pub monday_week: Option<u8>,
+    /// `%a`, `%u` or `%w`.
+    pub weekday: Option<Weekday>,
+    /// The hour of the day, by `%H`, or `%I` with `%p`.
+    pub hour: Option<u8>,
+    /// `%I`, from 1 to 12.
+    pub hour_12: Option<u8>,
+    /// `%p`, whether it's PM.
+    pub pm: Option<bool>,
+    /// `%M`.
+    pub minute: Option<u8>,
+    /// `%S`.
+    pub second: Option<u8>,
+    /// `%f`, in nanoseconds.
+    pub nanosecond: Option<u32>,
+    /// The number of digits `%f` was given with.
+    pub subsecond_digits: Option<u8>,
+    /// `%s`, in nanoseconds since the Unix epoch.
+    pub unix_timestamp_nanos: Option<i128>,
+    /// `%z` or `%Z`.
+    pub zone: Option<TimeZoneSpecifier<'a>>,
+}
+
+impl Default for Parsed<'_> {
+    fn default() -> Self {
+        Self {
+            calendar: &Gregorian,
+            year: None,
+            century: None,
+            year_of_century: None,
+            month: None,
+            day: None,
+            ordinal: None,
+            iso_year: None,
+            iso_year_of_century: None,
+            iso_week: None,
+            sunday_week: None,
+            monday_week: None,
+            weekday: None,
+            hour: None,
+            hour_12: None,
+            pm: None,
+            minute: None,
+            second: None,
+            nanosecond: None,
+            subsecond_digits: None,
+            unix_timestamp_nanos: None,
+            zone: None,
+        }
+    }
+}
+
+impl fmt::Debug for Parsed<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Parsed")
+            .field("year", &self.year)
+            .field("century", &self.century)
+            .field("year_of_century", &self.year_of_century)
+            .field("month", &self.month)
+            .field("day", &self.day)
+            .field("ordinal", &self.ordinal)
+            .field("iso_year", &self.iso_year)
+            .field("iso_year_of_century", &self.iso_year_of_century)
+            .field("iso_week", &self.iso_week)
+            .field("sunday_week", &self.sunday_week)
+            .field("monday_week", &self.monday_week)
+            .field("weekday", &self.weekday)
+            .field("hour", &self.hour)
+            .field("hour_12", &self.hour_12)
+            .field("pm", &self.pm)
+            .field("minute", &self.minute)
+            .field("second", &self.second)
+            .field("nanosecond", &self.nanosecond)
+            .field("subsecond_digits", &self.subsecond_digits)
+            .field("unix_timestamp_nanos", &self.unix_timestamp_nanos)
+            .field("zone", &self.zone)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Parsed<'_> {
+    /// The date time the timestamp determines, at the offset if given, or at UTC.
+    fn timestamp(&self) -> Result<Option<OffsetDateTime>, ParseError> {
+        let Some(timestamp) = self.unix_timestamp_nanos else {
+            return Ok(None);
+        };
+        let offset = match self.zone {
+            Some(TimeZoneSpecifier::Offset(offset)) => offset,
+            _ => UtcOffset::UTC,
+        };
+        // `%f` after `%s` is the fractional part of the second.
+        let nanos = timestamp_nanos(timestamp, self.nanosecond.unwrap_or(0), offset)?;
+        let date_time = OffsetDateTime::from_unix_timestamp_nanos(nanos)?;
+        Ok(Some(date_time.replace_offset(offset)))
+    }
+
+    /// The date, by the day of the year, the month and the day, the ISO 8601 week date, or the
+    /// week number and the weekday, in this order of preference.
+    pub fn to_date(&self) -> Result<Date, ParseError> {
+        if let Some(date_time) = self.timestamp()? {
+            return Ok(date_time.date());
+        }
+        let weekday = || {
+            self.weekday
+                .ok_or(ParseError::MissingComponent("day-of-week"))
+        };
+        if self.ordinal.is_none()
+            && self.month.is_none()
+            && (self.iso_week.is_some() || self.iso_year.is_some())
+        {
+            let week = self
+                .iso_week
+                .ok_or(ParseError::MissingComponent("iso-week-number"))?;
+            let weekday = weekday()?;
+            let iso_week_date =
+                |iso_year| Ok(Some(Date::from_iso_week_date(iso_year, week, weekday)?));
+            // The year, unlike the ISO 8601 week-based year, is in the calendar.
+            return match (self.iso_year, self.year) {
+                (Some(iso_year), _) => iso_week_date(iso_year),
+                (None, Some(year)) => in_gregorian_year(self.calendar, year, iso_week_date),
+                (None, None) => Err(ParseError::MissingComponent("iso-week-based-year")),
+            }?
+            .ok_or(ParseError::ComponentOutOfRange("date"));
+        }
+        let year = self.year.ok_or(ParseError::MissingComponent("year"))?;
+        if let Some(ordinal) = self.ordinal {
+            return self
+                .calendar
+                .ordinal_to_date(year, ordinal)
+                .ok_or(ParseError::ComponentOutOfRange("date"));
+        }
+        if let Some(month) = self.month {
+            let day = self
+                .day
+                .ok_or(ParseError::MissingComponent("day-of-month"))?;
+            return self
+                .calendar
+                .to_date(year, month, day)
+                .ok_or(ParseError::ComponentOutOfRange("date"));
+        }
+        let week = match (self.sunday_week, self.monday_week) {
+            (Some(week), _) => Some((week, Weekday::Sunday)),
+            (None, Some(week)) => Some((week, Weekday::Monday)),
+            (None, None) => None,
+        };
+        match week {
+            Some((week, first)) => {
+                let weekday = weekday()?;
+                in_gregorian_year(self.calendar, year, |year| {
+                    week_date(year, week, first, weekday)
+                })?
+                .ok_or(ParseError::ComponentOutOfRange("date"))
+            }
+            None => Err(ParseError::MissingComponent("month")),
+        }
+    }
+
+    /// The time, by the hour of the day, or of the half day with AM/PM.
+    pub fn to_time(&self) -> Result<Time, ParseError> {
+        if let Some(date_time) = self.timestamp()? {
+            return Ok(date_time.time());
+        }
+        let hour = match (self.hour, self.hour_12, self.pm) {
+            (Some(hour), _, _) => hour,
+            (None, Some(hour), Some(pm)) => hour % 12 + if pm { 12 } else { 0 },
+            (None, Some(_), None) => return Err(ParseError::MissingComponent("am/pm")),
+            (None, None, _) => return Err(ParseError::MissingComponent("hour-of-day")),
+        };
+        Ok(Time::from_hms_nano(
+            hour,
+            self.minute.unwrap_or(0),
+            self.second.unwrap_or(0),
+            self.nanosecond.unwrap_or(0),
+        )?)
+    }
+
+    /// The offset by `%z`. A timezone name isn't resolved.
+    pub fn to_offset(&self) -> Result<UtcOffset, ParseError> {
+        match self.zone {
+            Some(TimeZoneSpecifier::Offset(offset)) => Ok(offset),
+            _ => Err(ParseError::MissingComponent("offset")),
+        }
+    }
+
+    pub fn to_primitive_date_time(&self) -> Result<PrimitiveDateTime, ParseError> {
+        Ok(PrimitiveDateTime::new(self.to_date()?, self.to_time()?))
+    }
+
+    /// The date time at the offset, or at UTC if only the timestamp is given.
+    pub fn to_offset_date_time(&self) -> Result<OffsetDateTime, ParseError> {
+        if let Some(date_time) = self.timestamp()? {
+            return Ok(date_time);
+        }
+        Ok(self
+            .to_primitive_date_time()?
+            .assume_offset(self.to_offset()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::{
+        macros::{datetime, offset, time},
+        Month, Weekday,
+    };
+
+    use super::super::{
+        parse_fields, parse_fields_with_locale, ParseError, ParseOptions, TimeZoneSpecifier,
+    };
+    use crate::{
+        calendar::SolarHijri,
+        locale::{PosixLocale, WithCalendar},
+    };
+
+    #[test]
+    fn test_fields() -> Result<(), ParseError> {
+        let parsed = parse_fields("%y %j %I %p %M %f %z", "22 065 12 pm 34 50 +09:00")?;
+        assert_eq!(parsed.year, Some(2022));
+        assert_eq!(parsed.century, None);
+        assert_eq!(parsed.year_of_century, Some(22));
+        assert_eq!(parsed.month, None);
+        assert_eq!(parsed.ordinal, Some(65));
+        assert_eq!(parsed.hour, Some(12));
+        assert_eq!(parsed.hour_12, Some(12));
+        assert_eq!(parsed.pm, Some(true));
+        assert_eq!(parsed.second, None);
+        assert_eq!(parsed.nanosecond, Some(500_000_000));
+        assert_eq!(parsed.subsecond_digits, Some(2));
+        assert_eq!(parsed.zone, Some(TimeZoneSpecifier::Offset(offset!(+9))));
+        assert_eq!(
+            parsed.to_offset_date_time()?,
+            datetime!(2022-03-06 12:34:00.5 +9)
+        );
+
+        let parsed = parse_fields("%G-W%V-%u %U %W", "2022-W09-7 10 9")?;
+        assert_eq!(parsed.iso_year, Some(2022));
+        assert_eq!(parsed.iso_year_of_century, None);
+        assert_eq!(parsed.iso_week, Some(9));
+        assert_eq!(parsed.weekday, Some(Weekday::Sunday));
+        assert_eq!(parsed.sunday_week, None);
+        assert_eq!(parsed.monday_week, Some(9));
+        assert_eq!(parsed.to_date()?, datetime!(2022-03-06 00:00).date());
+
+        let parsed = parse_fields("%s.%f", "1646570096.5")?;
+        assert_eq!(parsed.year, None);
+        assert_eq!(
+            parsed.to_offset_date_time()?,
+            datetime!(2022-03-06 12:34:56.5 UTC)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_conversions() -> Result<(), ParseError> {
+        let mut parsed = parse_fields("%b %e %T", "Mar  6 12:34:56")?;
+        assert_eq!(parsed.month, Some(Month::March));
+        assert_eq!(parsed.to_time()?, time!(12:34:56));
+        assert_eq!(parsed.to_date(), Err(ParseError::MissingComponent("year")));
+        parsed.year = Some(2022);
+        assert_eq!(
+            parsed.to_primitive_date_time()?,
+            datetime!(2022-03-06 12:34:56)
+        );
+        assert_eq!(
+            parsed.to_offset_date_time(),
+            Err(ParseError::MissingComponent("offset"))
+        );
+
+        for (fmt, s, expected) in [
+            ("%Y %m", "2022 03", "day-of-month"),
+            ("%Y", "2022", "month"),
+            ("%Y %U", "2022 10", "day-of-week"),
+            ("%V %u", "09 7", "iso-week-based-year"),
+        ] {
+            assert_eq!(
+                parse_fields(fmt, s)?.to_date(),
+                Err(ParseError::MissingComponent(expected))
+            );
+        }
+        assert_eq!(
+            parse_fields("%I:%M", "12:34")?.to_time(),
+            Err(ParseError::MissingComponent("am/pm"))
+        );
+        assert_eq!(
+            parse_fields("%M:%S", "34:56")?.to_time(),
+            Err(ParseError::MissingComponent("hour-of-day"))
+        );
+        Ok(())
+    }
+    #[test]
+    fn test_calendar() -> Result<(), ParseError> {
+        let locale = WithCalendar::new(&PosixLocale, &SolarHijri);
+        let options = ParseOptions::default();
+        let parsed = parse_fields_with_locale("%Y/%m/%d", "1400/12/15", &locale, &options)?;
+        assert_eq!(parsed.year, Some(1400));
+        assert_eq!(parsed.month, Some(Month::December));
+        assert_eq!(parsed.to_date()?, datetime!(2022-03-06 00:00).date());
+
+        for (fmt, s) in [("%Y %j", "1400 351"), ("%Y %W %u", "1400 09 7")] {
+            assert_eq!(
+                parse_fields_with_locale(fmt, s, &locale, &options)?.to_date()?,
+                datetime!(2022-03-06 00:00).date()
+            );
+        }
+        assert_eq!(
+            parse_fields_with_locale("%Y/%m/%d", "1400/12/30", &locale, &options)?.to_date(),
+            Err(ParseError::ComponentOutOfRange("date"))
+        );
+        Ok(())
+    }
+}
@@ -3,7 +3,7 @@ use std::slice::SliceIndex;
 use thiserror::Error;
 use time::format_description::{modifier, Component, FormatItem};
 
-use super::desc_parser::Collector;
+use super::desc_parser::{Collector, Spec};
 
 #[derive(Error, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 #[non_exhaustive]
@@ -28,6 +28,21 @@ impl<'a> ToFormatItemCollector<'a> {
     }
 }
 
+/// `SubsecondDigits` of `%f`; the exact number of digits with a field width.
+fn subsecond_digits(spec: Spec) -> Result<modifier::SubsecondDigits, Error> {
+    use modifier::SubsecondDigits::*;
+
+    match spec.width {
+        None => Ok(modifier::Subsecond::default().digits),
+        Some(width) => [One, Two, Three, Four, Five, Six, Seven, Eight, Nine]
+            .get(width.wrapping_sub(1))
+            .copied()
+            .ok_or(Error::NoCorrespondingFormatItem(
+                "fractional digits other than 1 to 9",
+            )),
+    }
+}
+
 macro_rules! all_paddings {
     ($ret: expr, $create_base: expr, $component_builder: expr) => {
         const fn with_padding(pad: modifier::Padding) -> Component {
@@ -60,7 +75,7 @@ impl<'a> Collector for ToFormatItemCollector<'a> {
     }
 
     #[inline]
-    fn day_of_week_name(&mut self) -> Result<(), Self::Error> {
+    fn day_of_week_name(&mut self, _spec: Spec) -> Result<(), Self::Error> {
         const fn short() -> FormatItem<'static> {
             let mut short = modifier::Weekday::default();
             short.repr = modifier::WeekdayRepr::Short;
@@ -79,7 +94,7 @@ impl<'a> Collector for ToFormatItemCollector<'a> {
     }
 
     #[inline]
-    fn month_name(&mut self) -> Result<(), Self::Error> {
+    fn month_name(&mut self, _spec: Spec) -> Result<(), Self::Error> {
         const fn short() -> FormatItem<'static> {
             let mut short = modifier::Month::default();
             short.repr = modifier::MonthRepr::Short;
@@ -98,24 +113,24 @@ impl<'a> Collector for ToFormatItemCollector<'a> {
     }
 
     #[inline]
-    fn year_prefix(&mut self) -> Result<(), Self::Error> {
+    fn year_prefix(&mut self, _spec: Spec) -> Result<(), Self::Error> {
         Err(Self::Error::NoCorrespondingFormatItem("%C"))
     }
 
     #[inline]
-    fn day_of_month(&mut self) -> Result<(), Self::Error> {
+    fn day_of_month(&mut self, _spec: Spec) -> Result<(), Self::Error> {
         all_paddings!(self.items, modifier::Day::default(), Component::Day);
         Ok(())
     }
 
     #[inline]
-    fn hour_of_day(&mut self) -> Result<(), Self::Error> {
+    fn hour_of_day(&mut self, _spec: Spec) -> Result<(), Self::Error> {
         all_paddings!(self.items, modifier::Hour::default(), Component::Hour);
         Ok(())
     }
 
     #[inline]
-    fn hour_of_day_12(&mut self) -> Result<(), Self::Error> {
+    fn hour_of_day_12(&mut self, _spec: Spec) -> Result<(), Self::Error> {
         all_paddings!(
             self.items,
             {
@@ -129,25 +144,54 @@ impl<'a> Collector for ToFormatItemCollector<'a> {
     }
 
     #[inline]
-    fn day_of_year(&mut self) -> Result<(), Self::Error> {
+    fn iso8601_week_based_year_suffix(&mut self, _spec: Spec) -> Result<(), Self::Error> {
+        all_paddings!(
+            self.items,
+            {
+                let mut base = modifier::Year::default();
+                base.repr = modifier::YearRepr::LastTwo;
+                base.iso_week_based = true;
+                base
+            },
+            Component::Year
+        );
+        Ok(())
+    }
+
+    #[inline]
+    fn iso8601_week_based_year(&mut self, _spec: Spec) -> Result<(), Self::Error> {
+        all_paddings!(
+            self.items,
+            {
+                let mut base = modifier::Year::default();
+                base.iso_week_based = true;
+                base
+            },
+            Component::Year
+        );
+        Ok(())
+    }
+
+    #[inline]
+    fn day_of_year(&mut self, _spec: Spec) -> Result<(), Self::Error> {
         all_paddings!(self.items, modifier::Ordinal::default(), Component::Ordinal);
         Ok(())
     }
 
     #[inline]
-    fn month_of_year(&mut self) -> Result<(), Self::Error> {
+    fn month_of_year(&mut self, _spec: Spec) -> Result<(), Self::Error> {
         all_paddings!(self.items, modifier::Month::default(), Component::Month);
         Ok(())
     }
 
     #[inline]
-    fn minute_of_hour(&mut self) -> Result<(), Self::Error> {
+    fn minute_of_hour(&mut self, _spec: Spec) -> Result<(), Self::Error> {
         all_paddings!(self.items, modifier::Minute::default(), Component::Minute);
         Ok(())
     }
 
     #[inline]
-    fn ampm(&mut self) -> Result<(), Self::Error> {
+    fn ampm(&mut self, _spec: Spec) -> Result<(), Self::Error> {
         let mut modifier = modifier::Period::default();
         modifier.case_sensitive = false;
         self.items
@@ -156,14 +200,23 @@ impl<'a> Collector for ToFormatItemCollector<'a> {
     }
 
     #[inline]
-    fn second_of_minute(&mut self) -> Result<(), Self::Error> {
+    fn seconds_since_epoch(&mut self, _spec: Spec) -> Result<(), Self::Error> {
+        // `Component::UnixTimestamp` only exists in newer versions of the time crate.
+        Err(Self::Error::NoCorrespondingFormatItem(
+            "seconds since the epoch",
+        ))
+    }
+
+    #[inline]
+    fn second_of_minute(&mut self, _spec: Spec) -> Result<(), Self::Error> {
         all_paddings!(self.items, modifier::Second::default(), Component::Second);
         Ok(())
     }
 
     #[inline]
-    fn nanosecond_of_second(&mut self) -> Result<(), Self::Error> {
-        let modifier = modifier::Subsecond::default();
+    fn nanosecond_of_second(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        let mut modifier = modifier::Subsecond::default();
+        modifier.digits = subsecond_digits(spec)?;
         self.items
             .push(FormatItem::Component(Component::Subsecond(modifier)));
         Ok(())
@@ -175,7 +228,17 @@ impl<'a> Collector for ToFormatItemCollector<'a> {
     }
 
     #[inline]
-    fn week_number_of_current_year_start_sunday(&mut self) -> Result<(), Self::Error> {
+    fn day_of_week_from_monday_as_1(&mut self, _spec: Spec) -> Result<(), Self::Error> {
+        let mut modifier = modifier::Weekday::default();
+        modifier.repr = modifier::WeekdayRepr::Monday;
+        modifier.one_indexed = true;
+        self.items
+            .push(FormatItem::Component(Component::Weekday(modifier)));
+        Ok(())
+    }
+
+    #[inline]
+    fn week_number_of_current_year_start_sunday(&mut self, _spec: Spec) -> Result<(), Self::Error> {
         all_paddings!(
             self.items,
             {
@@ -189,7 +252,21 @@ impl<'a> Collector for ToFormatItemCollector<'a> {
     }
 
     #[inline]
-    fn day_of_week_from_sunday_as_0(&mut self) -> Result<(), Self::Error> {
+    fn iso8601_week_number(&mut self, _spec: Spec) -> Result<(), Self::Error> {
+        all_paddings!(
+            self.items,
+            {
+                let mut base = modifier::WeekNumber::default();
+                base.repr = modifier::WeekNumberRepr::Iso;
+                base
+            },
+            Component::WeekNumber
+        );
+        Ok(())
+    }
+
+    #[inline]
+    fn day_of_week_from_sunday_as_0(&mut self, _spec: Spec) -> Result<(), Self::Error> {
         let mut modifier = modifier::Weekday::default();
         modifier.repr = modifier::WeekdayRepr::Sunday;
         modifier.one_indexed = false;
@@ -199,7 +276,7 @@ impl<'a> Collector for ToFormatItemCollector<'a> {
     }
 
     #[inline]
-    fn week_number_of_current_year_start_monday(&mut self) -> Result<(), Self::Error> {
+    fn week_number_of_current_year_start_monday(&mut self, _spec: Spec) -> Result<(), Self::Error> {
         all_paddings!(
             self.items,
             {
@@ -213,7 +290,7 @@ impl<'a> Collector for ToFormatItemCollector<'a> {
     }
 
     #[inline]
-    fn year_suffix(&mut self) -> Result<(), Self::Error> {
+    fn year_suffix(&mut self, _spec: Spec) -> Result<(), Self::Error> {
         all_paddings!(
             self.items,
             {
@@ -227,27 +304,41 @@ impl<'a> Collector for ToFormatItemCollector<'a> {
     }
 
     #[inline]
-    fn year(&mut self) -> Result<(), Self::Error> {
+    fn year(&mut self, _spec: Spec) -> Result<(), Self::Error> {
         all_paddings!(self.items, modifier::Year::default(), Component::Year);
         Ok(())
     }
 
     #[inline]
-    fn timezone(&mut self) -> Result<(), Self::Error> {
+    fn timezone(&mut self, spec: Spec) -> Result<(), Self::Error> {
+        if spec.colons > 2 {
+            return Err(Self::Error::NoCorrespondingFormatItem("minimal offset"));
+        }
         let mut modifier = modifier::OffsetHour::default();
         modifier.sign_is_mandatory = true;
         self.items
             .push(FormatItem::Component(Component::OffsetHour(modifier)));
-        self.items
-            .push(FormatItem::Optional(&FormatItem::Literal(b":")));
+        // Unlike ours, `:` is mandatory for `%:z` and `%::z` and seconds are only for `%::z`.
+        if spec.colons == 0 {
+            self.items
+                .push(FormatItem::Optional(&FormatItem::Literal(b":")));
+        } else {
+            self.items.push(FormatItem::Literal(b":"));
+        }
         let modifier = modifier::OffsetMinute::default();
         self.items
             .push(FormatItem::Component(Component::OffsetMinute(modifier)));
+        if spec.colons == 2 {
+            self.items.push(FormatItem::Literal(b":"));
+            let modifier = modifier::OffsetSecond::default();
+            self.items
+                .push(FormatItem::Component(Component::OffsetSecond(modifier)));
+        }
         Ok(())
     }
 
     #[inline]
-    fn timezone_name(&mut self) -> Result<(), Self::Error> {
+    fn timezone_name(&mut self, _spec: Spec) -> Result<(), Self::Error> {
         Err(Self::Error::NoCorrespondingFormatItem("timezone name"))
     }
 
@@ -283,14 +374,17 @@ impl<'a> Collector for ToFormatItemCollector<'a> {
     }
 }
 
-pub fn parse_to_format_item(fmt: &str) -> Result<Vec<FormatItem>, Error> {
+pub fn parse_to_format_item(fmt: &str) -> Result<Vec<FormatItem<'_>>, Error> {
     let collector = ToFormatItemCollector::new(fmt.as_bytes());
     super::desc_parser::parse_format_specifications(fmt, collector, false)
 }
 
 #[cfg(test)]
 mod tests {
-    use time::{macros::datetime, OffsetDateTime, PrimitiveDateTime};
+    use time::{
+        macros::{datetime, time},
+        Date, OffsetDateTime, PrimitiveDateTime, Time,
+    };
 
     use super::parse_to_format_item;
 
@@ -303,6 +397,15 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn modifiers() -> Result<(), super::Error> {
+        assert_eq!(
+            parse_to_format_item("%EY-%Om-%Od %OH:%OM")?,
+            parse_to_format_item("%Y-%m-%d %H:%M")?,
+        );
+        Ok(())
+    }
+
     #[test]
     fn parse_primitive_datetime() -> Result<(), Box<dyn std::error::Error>> {
         let format_items = parse_to_format_item("%Y-%m-%d %H:%M:%S")?;
@@ -313,6 +416,27 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn iso8601_week_date() -> Result<(), Box<dyn std::error::Error>> {
+        let format_items = parse_to_format_item("%G-W%V-%u")?;
+        assert_eq!(
+            Date::parse("2020-W53-5", &format_items)?,
+            datetime!(2021-01-01 00:00).date()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn subsecond_digits() -> Result<(), Box<dyn std::error::Error>> {
+        let format_items = parse_to_format_item("%T.%3f")?;
+        assert_eq!(
+            Time::parse("12:09:14.120", &format_items)?,
+            time!(12:09:14.12)
+        );
+        assert!(Time::parse("12:09:14.12", &format_items).is_err());
+        Ok(())
+    }
+
     #[test]
     fn parse_offset_datetime() -> Result<(), Box<dyn std::error::Error>> {
         let format_items = parse_to_format_item("%Y-%m-%d %H:%M:%S %z")?;
@@ -324,6 +448,12 @@ mod tests {
             OffsetDateTime::parse("2012-05-21 12:09:14 +09:00", &format_items)?,
             datetime!(2012-05-21 12:09:14 +9:00)
         );
+        let format_items = parse_to_format_item("%F %T %::z")?;
+        assert_eq!(
+            OffsetDateTime::parse("2012-05-21 12:09:14 -00:30:15", &format_items)?,
+            datetime!(2012-05-21 12:09:14 -0:30:15)
+        );
+        assert!(OffsetDateTime::parse("2012-05-21 12:09:14 -0030", &format_items).is_err());
         Ok(())
     }
 }
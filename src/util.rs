@@ -1,4 +1,13 @@
-use time::{Month, Weekday};
+use time::{Date, Month, Weekday};
+
+/// `E` or `O` modifier that precedes a conversion specifier, like `%Ec` or `%Od`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Modifier {
+    /// `E`. Alternative era-based representation.
+    Era,
+    /// `O`. Alternative numeric symbols.
+    AlternativeDigits,
+}
 
 const MONTH_LONG: [&str; 12] = [
     "January",
@@ -17,7 +26,7 @@ const MONTH_LONG: [&str; 12] = [
 const MONTH_SHORT: [&str; 12] = [
     "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
 ];
-const MONTHS0: [Month; 12] = [
+pub(crate) const MONTHS: [Month; 12] = [
     Month::January,
     Month::February,
     Month::March,
@@ -40,19 +49,23 @@ const WEEKDAY_LONG: [&str; 7] = [
     "Saturday",
     "Sunday",
 ];
+pub(crate) const WEEKDAYS: [Weekday; 7] = [
+    Weekday::Monday,
+    Weekday::Tuesday,
+    Weekday::Wednesday,
+    Weekday::Thursday,
+    Weekday::Friday,
+    Weekday::Saturday,
+    Weekday::Sunday,
+];
 const WEEKDAY_SHORT: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
 const AMPM_UPPER: [&str; 2] = ["AM", "PM"];
-const AMPM_LOWER: [&str; 2] = ["am", "pm"];
 
 #[inline]
 pub(crate) const fn ampm_upper(hour: u8) -> &'static str {
     AMPM_UPPER[if hour < 12 { 0 } else { 1 }]
 }
 #[inline]
-pub(crate) const fn ampm_lower(hour: u8) -> &'static str {
-    AMPM_LOWER[if hour < 12 { 0 } else { 1 }]
-}
-#[inline]
 pub(crate) const fn month_long_str(month: Month) -> &'static str {
     MONTH_LONG[(month as u8 - 1) as usize]
 }
@@ -70,11 +83,69 @@ pub(crate) const fn weekday_short_str(weekday: Weekday) -> &'static str {
 }
 #[inline]
 pub(crate) const fn get_month(month: u8) -> Option<Month> {
-    // TODO: Use MONTHD0.get((month - 1) as usize).copied() once copied() get to a const fn in
+    // TODO: Use MONTHS.get((month - 1) as usize).copied() once copied() get to a const fn in
     // stable.
     if 1 <= month && month <= 12 {
-        Some(MONTHS0[(month - 1) as usize])
+        Some(MONTHS[(month - 1) as usize])
     } else {
         None
     }
 }
+
+/// The given date, panicking if there is no such date, for constants.
+pub(crate) const fn date(year: i32, month: Month, day: u8) -> Date {
+    match Date::from_calendar_date(year, month, day) {
+        Ok(date) => date,
+        Err(_) => panic!("invalid date"),
+    }
+}
+
+/// Strips `prefix` from `s` ignoring the case. Both are compared case folded as streams of
+/// characters, so that a character folding into more than one matches them, like `ß` and `SS`.
+#[inline]
+pub(crate) fn strip_prefix_ignore_case<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+    let mut prefix = prefix.chars().flat_map(fold_case).peekable();
+    let mut chars = s.chars();
+    while prefix.peek().is_some() {
+        // The prefix has to end at the end of a character of `s`.
+        if !fold_case(chars.next()?).all(|c| prefix.next() == Some(c)) {
+            return None;
+        }
+    }
+    Some(chars.as_str())
+}
+
+/// The case folding of `c`, approximated by the lower case of its upper case, as the standard
+/// library has no case folding. It unifies e.g. `ß` and `ss`, or `ς`, `σ` and `Σ`.
+#[inline]
+fn fold_case(c: char) -> impl Iterator<Item = char> {
+    c.to_uppercase().flat_map(char::to_lowercase)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::strip_prefix_ignore_case;
+
+    #[test]
+    fn test_strip_prefix_ignore_case() {
+        for (s, prefix, expected) in [
+            ("März 2022", "MÄRZ", Some(" 2022")),
+            ("mÄrz", "Mär", Some("z")),
+            ("MÄSSIG", "mäßig", Some("")),
+            ("mäßig", "MÄSSIG", Some("")),
+            ("ΟΔΟΣ.", "οδος", Some(".")),
+            ("οδός", "ΟΔΌΣ", Some("")),
+            ("mäß", "MÄS", None),
+            ("Mä", "März", None),
+            ("Mai", "Mär", None),
+        ] {
+            assert_eq!(
+                strip_prefix_ignore_case(s, prefix),
+                expected,
+                "{} {}",
+                s,
+                prefix
+            );
+        }
+    }
+}